@@ -1,8 +1,13 @@
 use std::collections::HashMap;
+use std::path::Path;
 
 use crate::{
-    configuration::{get_db_file, Settings},
+    configuration::{Paths, Settings},
+    error::StorageError,
+    history::{History, Operation, OperationKind},
+    storage::{self, TaskStore},
     task::Task,
+    trash::{Trash, TrashedTask},
     utils,
 };
 
@@ -12,72 +17,319 @@ pub struct App {
     pub tasks: HashMap<Id, Task>,
     pub settings: Settings,
     pub current_id: usize,
+    /// A message for the user about something that happened in the background
+    pub notice: Option<String>,
+    store: Box<dyn TaskStore>,
+    history: History,
+    trash: Trash,
 }
 
 impl App {
-    pub fn new(settings: Settings) -> App {
-        let tasks: HashMap<Id, Task> = utils::load_tasks(get_db_file());
+    pub fn new(settings: Settings, paths: &Paths) -> Result<App, StorageError> {
+        let db_file = paths.db_file(&settings.storage);
+        let history = History::open(
+            utils::path_with_suffix(&db_file, "history"),
+            settings.history_size,
+        )?;
+        let trash = Trash::open(
+            utils::path_with_suffix(&db_file, "trash"),
+            settings.trash_retention_days,
+        )?;
+        let store = storage::open_store(&settings.storage, paths)?;
+
+        let mut app = App::with_store(settings, store)?;
+        app.history = history;
+        app.trash = trash;
+        Ok(app)
+    }
+
+    pub fn with_store(
+        settings: Settings,
+        mut store: Box<dyn TaskStore>,
+    ) -> Result<App, StorageError> {
+        let tasks: HashMap<Id, Task> = store.load()?;
         let current_id = tasks.iter().map(|(&k, _)| k).max().unwrap_or(0);
-        App {
+        let history = History::in_memory(settings.history_size);
+        Ok(App {
             tasks,
             settings,
             current_id,
+            notice: None,
+            store,
+            history,
+            trash: Trash::in_memory(),
+        })
+    }
+
+    /// Reloads the tasks if another process changed them. Returns whether it did
+    pub fn reload_if_changed(&mut self) -> Result<bool, StorageError> {
+        if !self.store.has_changed()? {
+            return Ok(false);
         }
+        self.reload()?;
+        Ok(true)
+    }
+
+    fn reload(&mut self) -> Result<(), StorageError> {
+        self.tasks = self.store.load()?;
+        let max_id = self.tasks.keys().copied().max().unwrap_or(0);
+        self.current_id = self.current_id.max(max_id);
+        self.notice =
+            Some("Tasks were changed by another process and have been reloaded".to_string());
+        Ok(())
+    }
+
+    /// Writes a changed task to the store. The store applies it on top of whatever other
+    /// processes saved since the last reload, so that is picked up as well.
+    fn save_task(&mut self, task: &Task) -> Result<(), StorageError> {
+        self.store.update(task)?;
+        self.tasks.insert(storage::task_id(task), task.clone());
+        self.reload_if_changed()?;
+        Ok(())
     }
 
     pub fn get_task(&self, id: Id) -> Option<&Task> {
         self.tasks.get(&id)
     }
 
-    pub fn save_state(&mut self) {
-        utils::save_tasks(get_db_file(), self);
+    pub fn add_task(&mut self, t: Task) -> Result<Id, StorageError> {
+        self.insert_task(t, OperationKind::Add)
     }
 
-    pub fn add_task(&mut self, mut t: Task) -> Id {
-        let new_id = match t.id {
-            Some(id) => {
-                self.tasks.insert(id, t);
-                self.save_state();
-                id
-            }
-            None => {
-                let new_id = self.get_next_id();
-                t.id = Some(new_id);
-                self.tasks.insert(new_id, t);
-                new_id
-            },
+    /// Moves the last deleted task with this id out of the trash. Returns its new id, or
+    /// `None` if it isn't in the trash
+    pub fn restore_task(&mut self, id: Id) -> Result<Option<Id>, StorageError> {
+        let Some(trashed) = self.trash.take(id)? else {
+            return Ok(None);
         };
-        self.save_state();
-        new_id
+        self.insert_task(trashed.task, OperationKind::Restore)
+            .map(Some)
     }
 
-    pub fn delete_task(&mut self, id: usize) -> Option<Id> {
-        self.tasks.remove(&id)?;
-        self.save_state();
-        Some(id)
+    /// The deleted tasks, most recently deleted first
+    pub fn trashed_tasks(&mut self) -> Result<Vec<TrashedTask>, StorageError> {
+        self.trash.list()
     }
 
-    pub fn set_complete(&mut self, id: usize, complete: bool) -> Option<Id> {
-        let new_task_id = if complete {
-            let possible_new_task = self.tasks.get_mut(&id)?.set_complete();
-            if let Some(possible_new_task) = possible_new_task {
-                self.delete_task(id);
-                self.add_task(possible_new_task)
-            } else {
-                id
-            }
+    /// Deletes the tasks in the trash for good. Returns how many there were
+    pub fn empty_trash(&mut self) -> Result<usize, StorageError> {
+        self.trash.empty()
+    }
+
+    fn insert_task(&mut self, mut t: Task, kind: OperationKind) -> Result<Id, StorageError> {
+        // Pick up tasks added elsewhere first, so we don't hand out an id that's taken
+        self.reload_if_changed()?;
+        match t.id {
+            Some(id) if !self.tasks.contains_key(&id) => {}
+            // Restored tasks get a new id if theirs was handed out again
+            _ => t.id = Some(self.get_next_id()),
+        }
+        let new_id = self.store.insert(&t)?;
+
+        if t.id != Some(new_id) {
+            // Someone else took the id between our last reload and the write
+            t.id = Some(new_id);
+            self.reload()?;
         } else {
-            self.tasks.get_mut(&id)?.set_incomplete();
-            id
+            self.tasks.insert(new_id, t.clone());
+            self.reload_if_changed()?;
+        }
+        self.current_id = self.current_id.max(new_id);
+
+        self.history.record(Operation {
+            kind,
+            id: new_id,
+            before: None,
+            after: Some(t),
+        })?;
+        Ok(new_id)
+    }
+
+    /// Replaces the task with the same id
+    pub fn update_task(&mut self, task: Task) -> Result<Option<Id>, StorageError> {
+        self.reload_if_changed()?;
+        let Some(id) = task.id else {
+            return Ok(None);
+        };
+        let Some(before) = self.tasks.get(&id).cloned() else {
+            return Ok(None);
         };
 
-        self.save_state();
-        Some(new_task_id)
+        self.save_task(&task)?;
+        self.history.record(Operation {
+            kind: OperationKind::Edit,
+            id,
+            before: Some(before),
+            after: Some(task),
+        })?;
+        Ok(Some(id))
+    }
+
+    pub fn delete_task(&mut self, id: usize) -> Result<Option<Id>, StorageError> {
+        self.reload_if_changed()?;
+        let Some(before) = self.tasks.get(&id).cloned() else {
+            return Ok(None);
+        };
+        // Trash it first, so a failure in between can't lose the task
+        self.trash.add(before.clone())?;
+        if let Err(e) = self.store.delete(id) {
+            // The task is still there, so it shouldn't be in the trash as well. Failing
+            // to take it out only leaves a copy behind, so report the original error
+            let _ = self.trash.take(id);
+            return Err(e);
+        }
+        self.tasks.remove(&id);
+        self.reload_if_changed()?;
+        self.history.record(Operation {
+            kind: OperationKind::Delete,
+            id,
+            before: Some(before),
+            after: None,
+        })?;
+        Ok(Some(id))
+    }
+
+    /// Marks a task as complete or incomplete. With `catch_up`, a repeating task skips
+    /// the occurrences that are overdue already
+    pub fn set_complete(
+        &mut self,
+        id: usize,
+        complete: bool,
+        catch_up: bool,
+    ) -> Result<Option<Id>, StorageError> {
+        self.reload_if_changed()?;
+        let Some(before) = self.tasks.get(&id).cloned() else {
+            return Ok(None);
+        };
+
+        // A repeating task turns into its next occurrence in place, so its id doesn't change
+        let mut task = before.clone();
+        let (mut task, kind) = if complete {
+            (
+                task.set_complete(catch_up).unwrap_or(task),
+                OperationKind::Complete,
+            )
+        } else {
+            task.set_incomplete();
+            (task, OperationKind::Uncomplete)
+        };
+        task.trim_completions(self.settings.completion_history);
+
+        self.save_task(&task)?;
+        self.history.record(Operation {
+            kind,
+            id,
+            before: Some(before),
+            after: Some(task),
+        })?;
+        Ok(Some(id))
+    }
+
+    pub fn toggle_complete_task(
+        &mut self,
+        id: usize,
+        catch_up: bool,
+    ) -> Result<Option<Id>, StorageError> {
+        let Some(task) = self.tasks.get(&id) else {
+            return Ok(None);
+        };
+        let complete = task.complete;
+        self.set_complete(id, !complete, catch_up)
+    }
+
+    /// Moves a repeating task on to its next occurrence without completing it. Returns
+    /// `None` if the task doesn't exist or has no next occurrence
+    pub fn skip_occurrence(&mut self, id: usize) -> Result<Option<Id>, StorageError> {
+        self.reload_if_changed()?;
+        let Some(before) = self.tasks.get(&id).cloned() else {
+            return Ok(None);
+        };
+        let Some(task) = before.skip() else {
+            return Ok(None);
+        };
+
+        self.save_task(&task)?;
+        self.history.record(Operation {
+            kind: OperationKind::Skip,
+            id,
+            before: Some(before),
+            after: Some(task),
+        })?;
+        Ok(Some(id))
+    }
+
+    /// Reverts the last operation. Returns it, or `None` if there was nothing to undo
+    pub fn undo(&mut self) -> Result<Option<Operation>, StorageError> {
+        let _lock = self.history.lock()?;
+        self.reload_if_changed()?;
+        let Some(operation) = self.history.pop_undo()? else {
+            return Ok(None);
+        };
+        self.revert_to(operation.id, &operation.after, &operation.before)?;
+        match (operation.kind, &operation.after) {
+            (OperationKind::Delete, _) => {
+                self.trash.take(operation.id)?;
+            }
+            (OperationKind::Restore, Some(task)) => self.trash.add(task.clone())?,
+            _ => {}
+        }
+        self.history.push_redo(operation.clone())?;
+        Ok(Some(operation))
+    }
+
+    /// Applies the last undone operation again. Returns it, or `None` if there was
+    /// nothing to redo
+    pub fn redo(&mut self) -> Result<Option<Operation>, StorageError> {
+        let _lock = self.history.lock()?;
+        self.reload_if_changed()?;
+        let Some(operation) = self.history.pop_redo()? else {
+            return Ok(None);
+        };
+        self.revert_to(operation.id, &operation.before, &operation.after)?;
+        match (operation.kind, &operation.before) {
+            (OperationKind::Delete, Some(task)) => self.trash.add(task.clone())?,
+            (OperationKind::Restore, _) => {
+                self.trash.take(operation.id)?;
+            }
+            _ => {}
+        }
+        self.history.push_undo(operation.clone())?;
+        Ok(Some(operation))
     }
 
-    pub fn toggle_complete_task(&mut self, id: usize) -> Option<Id> {
-        let complete = self.tasks.get(&id)?.complete;
-        self.set_complete(id, !complete)
+    /// Moves task `id` from `from` to `to`. Fails without changing anything
+    /// if the task isn't in the `from` state anymore, since that would overwrite changes
+    /// made after the operation.
+    fn revert_to(
+        &mut self,
+        id: Id,
+        from: &Option<Task>,
+        to: &Option<Task>,
+    ) -> Result<(), StorageError> {
+        if self.tasks.get(&id) != from.as_ref() {
+            self.history.save()?;
+            return Err(StorageError::Conflict {
+                path: self
+                    .history
+                    .file()
+                    .map(Path::to_path_buf)
+                    .unwrap_or_default(),
+                id,
+            });
+        }
+
+        match to {
+            Some(task) => {
+                self.save_task(task)?;
+                self.current_id = self.current_id.max(id);
+            }
+            None => {
+                self.store.delete(id)?;
+                self.tasks.remove(&id);
+                self.reload_if_changed()?;
+            }
+        }
+        Ok(())
     }
 
     fn get_next_id(&mut self) -> usize {
@@ -85,3 +337,142 @@ impl App {
         self.current_id
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{repeat::Repeat, storage::MemoryStore};
+    use chrono::{Days, Local, TimeZone};
+
+    fn app_with(tasks: Vec<Task>) -> App {
+        let store = Box::new(MemoryStore::new(numbered(tasks)));
+        App::with_store(Settings::default(), store).unwrap()
+    }
+
+    fn numbered(tasks: Vec<Task>) -> HashMap<Id, Task> {
+        tasks
+            .into_iter()
+            .enumerate()
+            .map(|(i, mut task)| {
+                task.set_id(Some(i + 1));
+                (i + 1, task)
+            })
+            .collect()
+    }
+
+    /// A store that can't delete anything
+    struct ReadOnlyStore(MemoryStore);
+
+    impl TaskStore for ReadOnlyStore {
+        fn load(&mut self) -> Result<HashMap<Id, Task>, StorageError> {
+            self.0.load()
+        }
+
+        fn insert(&mut self, task: &Task) -> Result<Id, StorageError> {
+            self.0.insert(task)
+        }
+
+        fn update(&mut self, task: &Task) -> Result<(), StorageError> {
+            self.0.update(task)
+        }
+
+        fn delete(&mut self, _id: Id) -> Result<(), StorageError> {
+            Err(StorageError::Database {
+                path: "tasks.db".into(),
+                message: "attempt to write a readonly database".to_string(),
+            })
+        }
+
+        fn list(&self) -> Result<Vec<Task>, StorageError> {
+            self.0.list()
+        }
+    }
+
+    fn task(name: &str, repeats: Repeat) -> Task {
+        let mut task = Task::default();
+        task.set_name(name.to_string());
+        task.set_date(Local.with_ymd_and_hms(2030, 1, 10, 12, 0, 0).unwrap());
+        task.set_has_time(true);
+        task.set_repeats(repeats);
+        task
+    }
+
+    fn stored(app: &App, id: Id) -> Task {
+        let tasks = app.store.list().unwrap();
+        tasks.into_iter().find(|t| t.id == Some(id)).unwrap()
+    }
+
+    #[test]
+    fn completing_a_task_marks_it_complete() {
+        let mut app = app_with(vec![task("once", Repeat::Never)]);
+        assert_eq!(app.set_complete(1, true, false).unwrap(), Some(1));
+
+        assert!(app.get_task(1).unwrap().complete);
+        assert_eq!(&stored(&app, 1), app.get_task(1).unwrap());
+    }
+
+    #[test]
+    fn completing_a_repeating_task_moves_it_to_the_next_occurrence() {
+        let mut app = app_with(vec![task("daily", Repeat::Daily)]);
+        let due = app.get_task(1).unwrap().date.unwrap();
+        assert_eq!(app.set_complete(1, true, false).unwrap(), Some(1));
+
+        let next = app.get_task(1).unwrap();
+        assert_eq!(app.tasks.len(), 1);
+        assert!(!next.complete);
+        assert_eq!(next.date, Some(due + Days::new(1)));
+        assert_eq!(&stored(&app, 1), next);
+    }
+
+    #[test]
+    fn uncompleting_a_task_marks_it_incomplete() {
+        let mut app = app_with(vec![task("once", Repeat::Never)]);
+        app.set_complete(1, true, false).unwrap();
+        assert_eq!(app.set_complete(1, false, false).unwrap(), Some(1));
+
+        assert!(!app.get_task(1).unwrap().complete);
+        assert!(!stored(&app, 1).complete);
+    }
+
+    #[test]
+    fn completing_a_missing_task_does_nothing() {
+        let mut app = app_with(vec![task("once", Repeat::Never)]);
+        assert_eq!(app.set_complete(2, true, false).unwrap(), None);
+        assert!(!app.get_task(1).unwrap().complete);
+    }
+
+    #[test]
+    fn completing_a_task_can_be_undone() {
+        let mut app = app_with(vec![task("daily", Repeat::Daily)]);
+        let before = app.get_task(1).unwrap().clone();
+        app.set_complete(1, true, false).unwrap();
+        app.undo().unwrap();
+
+        assert_eq!(app.get_task(1), Some(&before));
+        assert_eq!(stored(&app, 1), before);
+    }
+
+    #[test]
+    fn deleting_a_task_moves_it_to_the_trash() {
+        let mut app = app_with(vec![task("once", Repeat::Never)]);
+        assert_eq!(app.delete_task(1).unwrap(), Some(1));
+
+        assert!(app.get_task(1).is_none());
+        assert!(app.store.list().unwrap().is_empty());
+        let trashed = app.trashed_tasks().unwrap();
+        assert_eq!(trashed.len(), 1);
+        assert_eq!(trashed[0].task.name, "once");
+    }
+
+    #[test]
+    fn failed_delete_leaves_the_trash_alone() {
+        let tasks = numbered(vec![task("once", Repeat::Never)]);
+        let store = ReadOnlyStore(MemoryStore::new(tasks));
+        let mut app = App::with_store(Settings::default(), Box::new(store)).unwrap();
+        assert!(app.delete_task(1).is_err());
+
+        assert!(app.get_task(1).is_some());
+        assert!(app.trashed_tasks().unwrap().is_empty());
+        assert!(app.undo().unwrap().is_none());
+    }
+}
@@ -4,18 +4,37 @@ use clap::Parser;
 use crate::app::App;
 use crate::cli::cli_utils;
 use crate::cli::formats::Format;
-use crate::task_form::TaskForm;
+use crate::task_form::{self, TaskForm};
+use crate::utils;
 
 #[derive(Parser)]
 pub struct Args {
     /// The name of the new task
     name: String,
-    /// The date the task is due
+    /// The date the task is due, in the input date format or like "tomorrow", "next fri 5pm",
+    /// "in 3 days" or "dec 24"
     #[arg(long)]
     date: Option<String>,
-    /// How often the task repeats
+    /// The time zone the due time is in, like "America/New_York". Defaults to the local one
+    #[arg(long)]
+    timezone: Option<String>,
+    /// The day to start working on the task. It's hidden until then
+    #[arg(long)]
+    starts: Option<String>,
+    /// How often the task repeats, e.g. "daily", "mon,wed", "every 2 weeks on tue,thu" or an
+    /// iCalendar RRULE like "FREQ=MONTHLY;BYDAY=-1FR"
     #[arg(long)]
     repeats: Option<String>,
+    /// What the next occurrence of a repeating task is counted from: "due date" (the
+    /// default) or "completion date"
+    #[arg(long)]
+    repeats_from: Option<String>,
+    /// The last date the task repeats on
+    #[arg(long)]
+    until: Option<String>,
+    /// How many times the task is due in total, counting the first time
+    #[arg(long)]
+    count: Option<u32>,
     /// The group the task belongs to
     #[arg(long)]
     group: Option<String>,
@@ -28,6 +47,9 @@ pub struct Args {
     /// The format to display the new task with
     #[arg(long)]
     format: Option<Format>,
+    /// Prints the first due dates of the task instead of adding it
+    #[arg(long)]
+    dry_run: bool,
 }
 
 pub fn run(mut app: App, args: Args) -> Result<()> {
@@ -35,24 +57,60 @@ pub fn run(mut app: App, args: Args) -> Result<()> {
         name,
         format,
         date,
+        timezone,
+        starts,
         repeats,
+        repeats_from,
+        until,
+        count,
         group,
         description,
         url,
+        dry_run,
     } = args;
 
     let mut task_form = TaskForm {
         id: None,
+        series_id: None,
         name,
         date: date.unwrap_or("".to_string()),
+        timezone: timezone.unwrap_or("".to_string()),
+        start_date: starts.unwrap_or("".to_string()),
         repeats: repeats.unwrap_or("".to_string()),
+        repeats_from: repeats_from.unwrap_or("".to_string()),
+        repeats_until: until.unwrap_or("".to_string()),
+        occurrences_left: count.map(|c| c.to_string()).unwrap_or("".to_string()),
         group: group.unwrap_or("".to_string()),
         description: description.unwrap_or("".to_string()),
         url: url.unwrap_or("".to_string()),
     };
 
+    if dry_run {
+        let task = task_form.parse(&app.settings)?;
+        let dates: Vec<_> = task.due_dates().take(task_form::PREVIEW_LEN).collect();
+        let json = || {
+            dates
+                .iter()
+                .map(|d| d.format("%+").to_string())
+                .collect::<Vec<String>>()
+        };
+        match format {
+            Some(Format::Json) => println!("{}", serde_json::to_string(&json())?),
+            Some(Format::JsonPretty) => println!("{}", serde_json::to_string_pretty(&json())?),
+            _ => {
+                for date in &dates {
+                    println!(
+                        "{}",
+                        utils::date_to_display_str(date, task.has_time, &app.settings)
+                    );
+                }
+            }
+        }
+        return Ok(());
+    }
+
     let task = task_form.submit(&app.settings)?;
-    let id = app.add_task(task);
+    let id = app.add_task(task)?;
     let task = app.get_task(id).unwrap();
     cli_utils::print_task(task, format, &app.settings);
 
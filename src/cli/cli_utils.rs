@@ -1,9 +1,68 @@
 use super::formats::Format;
-use crate::{configuration::Settings, task::Task, utils};
+use crate::{app::Id, configuration::Settings, repeat::Repeat, task::Task, utils};
+use anyhow::{anyhow, Result};
+use itertools::Itertools;
+
+// How many past completions to list under a task in plain text
+const RECENT_COMPLETIONS: usize = 5;
+// Shorter series id prefixes match too many tasks by accident
+const MIN_SERIES_ID_PREFIX: usize = 4;
+// How much of the series id is shown in plain text
+const SHORT_SERIES_ID: usize = 8;
+
+/// Finds the task an `--id` argument refers to. It can be the task's id, its series id,
+/// or a unique prefix of the series id of at least `MIN_SERIES_ID_PREFIX` characters.
+/// Numbers are always read as ids, even if a series id starts with them.
+pub fn find_task_id<'a>(
+    tasks: impl IntoIterator<Item = &'a Task>,
+    query: &str,
+) -> Result<Option<Id>> {
+    let tasks: Vec<&Task> = tasks.into_iter().collect();
+    if query.chars().all(|c| c.is_ascii_digit()) {
+        let id = query.parse::<Id>().ok();
+        return Ok(id.filter(|&id| tasks.iter().any(|t| t.id == Some(id))));
+    }
+    if query.len() < MIN_SERIES_ID_PREFIX {
+        return Err(anyhow!(
+            "'{}' is too short, use at least {} characters of the series id",
+            query,
+            MIN_SERIES_ID_PREFIX
+        ));
+    }
+
+    let prefix = query.to_lowercase();
+    let matches: Vec<Id> = tasks
+        .iter()
+        .filter(|t| t.series_id.to_string().starts_with(&prefix))
+        .map(|t| t.id.unwrap())
+        .unique()
+        .collect();
+    match matches.as_slice() {
+        [] => Ok(None),
+        [id] => Ok(Some(*id)),
+        _ => Err(anyhow!(
+            "'{}' matches {} tasks, use a longer prefix",
+            query,
+            matches.len()
+        )),
+    }
+}
+
+/// The start of the series id, as shown in plain text. It is never all digits, so that
+/// `find_task_id` doesn't take it for an id.
+pub fn short_series_id(task: &Task) -> String {
+    let series_id = task.series_id.to_string();
+    let short = &series_id[..SHORT_SERIES_ID];
+    match short.chars().all(|c| c.is_ascii_digit()) {
+        // Up to and including the first dash
+        true => series_id[..=SHORT_SERIES_ID].to_string(),
+        false => short.to_string(),
+    }
+}
 
 pub fn print_task(task: &Task, format: Option<Format>, settings: &Settings) {
     let tasks = vec![task];
-    print_tasks(tasks, format, true, true, settings);
+    print_tasks(tasks, format, true, true, true, settings);
 }
 
 pub fn print_tasks(
@@ -11,16 +70,24 @@ pub fn print_tasks(
     format: Option<Format>,
     show_descriptions: bool,
     show_urls: bool,
+    show_history: bool,
     settings: &Settings,
 ) {
     match format {
-        Some(Format::Json) => println!("{}", serde_json::to_string(&tasks).expect("Failed to serialize tasks to JSON")),
-        Some(Format::JsonPretty) => println!("{}", serde_json::to_string_pretty(&tasks).expect("Failed to serialize tasks to JSON")),
+        Some(Format::Json) => println!(
+            "{}",
+            serde_json::to_string(&tasks).expect("Failed to serialize tasks to JSON")
+        ),
+        Some(Format::JsonPretty) => println!(
+            "{}",
+            serde_json::to_string_pretty(&tasks).expect("Failed to serialize tasks to JSON")
+        ),
         _ => {
-            let longest_name = tasks.iter().map(|t| t.name.len()).max().unwrap_or(0);
+            let longest_name =
+                tasks.iter().map(|t| t.name.len()).max().unwrap_or(0) + SHORT_SERIES_ID + 3;
             let longest_date = tasks
                 .iter()
-                .map(|t| utils::date_to_display_str(&t.date, settings).len())
+                .map(|t| utils::due_to_display_str(t, settings).len())
                 .max()
                 .unwrap_or(0);
             let longest_repeat = tasks
@@ -47,6 +114,10 @@ pub fn print_tasks(
             if show_urls {
                 print!("Url  ");
             }
+
+            if show_history {
+                print!("Streak  ");
+            }
             println!();
 
             // Print tasks
@@ -55,11 +126,11 @@ pub fn print_tasks(
                 let x = settings.icons.get_complete_icon(complete);
                 let name = task.name.clone();
                 let id = task.id.unwrap();
-                let name_id = format!("{} {} ({})", x, name, id);
+                let name_id = format!("{} {} ({}, {})", x, name, id, short_series_id(task));
                 let width = longest_name + 10;
                 print!("{:width$}  ", name_id, width = width);
 
-                let date = utils::date_to_display_str(&task.date, settings);
+                let date = utils::due_to_display_str(task, settings);
                 print!("{:width$}  ", date, width = longest_date);
 
                 let repeats = &task.repeats;
@@ -78,8 +149,94 @@ pub fn print_tasks(
                     print!("{}  ", url.unwrap_or(String::from("")));
                 }
 
+                if show_history && task.repeats != Repeat::Never {
+                    print!("{} (best {})  ", task.streak(), task.best_streak());
+                }
+
                 println!();
+
+                if show_history {
+                    for completion in task.completions.iter().rev().take(RECENT_COMPLETIONS) {
+                        let completed_at =
+                            utils::past_to_human_str(&completion.completed_at, true, settings);
+                        match completion.on_time {
+                            true => println!("      done {}, on time", completed_at),
+                            false => println!(
+                                "      done {}, late (due {})",
+                                completed_at,
+                                utils::date_to_display_str(
+                                    &completion.due,
+                                    task.has_time,
+                                    settings
+                                )
+                            ),
+                        }
+                    }
+                }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn task(id: Id, series_id: &str) -> Task {
+        let mut task = Task::default();
+        task.set_id(Some(id));
+        task.series_id = Uuid::parse_str(series_id).unwrap();
+        task
+    }
+
+    fn tasks() -> Vec<Task> {
+        vec![
+            task(1, "12345678-aaaa-4aaa-8aaa-aaaaaaaaaaaa"),
+            task(2, "abcd1234-bbbb-4bbb-8bbb-bbbbbbbbbbbb"),
+            task(3, "abce5678-cccc-4ccc-8ccc-cccccccccccc"),
+        ]
+    }
+
+    #[test]
+    fn numbers_are_always_ids() {
+        let tasks = tasks();
+        assert_eq!(find_task_id(&tasks, "2").unwrap(), Some(2));
+        assert_eq!(find_task_id(&tasks, "1234").unwrap(), None);
+        assert_eq!(
+            find_task_id(&tasks, "99999999999999999999999").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn series_id_prefixes_need_four_characters() {
+        let tasks = tasks();
+        assert!(find_task_id(&tasks, "abc").is_err());
+        assert!(find_task_id(&tasks, "").unwrap().is_none());
+        assert_eq!(find_task_id(&tasks, "ABCD").unwrap(), Some(2));
+        assert_eq!(find_task_id(&tasks, "1234-").unwrap(), None);
+        assert_eq!(find_task_id(&tasks, "12345678-").unwrap(), Some(1));
+    }
+
+    #[test]
+    fn ambiguous_prefixes_are_rejected() {
+        let mut tasks = tasks();
+        assert!(find_task_id(&tasks, "abcd").is_ok());
+        tasks.push(task(4, "abcd9999-dddd-4ddd-8ddd-dddddddddddd"));
+        assert!(find_task_id(&tasks, "abcd").is_err());
+        assert_eq!(find_task_id(&tasks, "abcd1").unwrap(), Some(2));
+    }
+
+    #[test]
+    fn short_series_ids_find_their_task() {
+        let tasks = tasks();
+        for task in &tasks {
+            let short = short_series_id(task);
+            assert!(!short.chars().all(|c| c.is_ascii_digit()));
+            assert_eq!(find_task_id(&tasks, &short).unwrap(), task.id);
+        }
+        assert_eq!(short_series_id(&tasks[0]), "12345678-");
+        assert_eq!(short_series_id(&tasks[1]), "abcd1234");
+    }
+}
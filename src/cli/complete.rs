@@ -1,16 +1,21 @@
 use super::{cli_utils, formats::Format};
-use crate::app::App;
-use anyhow::Result;
+use crate::{app::App, configuration::OverdueRepeats};
+use anyhow::{anyhow, Result};
 use clap::{Parser, ValueEnum};
+use std::io::{self, IsTerminal, Write};
 
 #[derive(Parser)]
 pub struct Args {
-    /// The ID of the task to modify
+    /// The ID of the task to modify, or its series id (or a unique prefix of 4+ characters)
     #[arg(short, long)]
-    id: usize,
+    id: String,
     /// Whether the task should be marked as complete or incomplete
     #[arg(short, long)]
     complete: CompleteStatus,
+    /// What to do if more occurrences of a repeating task are overdue. Defaults to the
+    /// `overdue_repeats` setting
+    #[arg(long)]
+    overdue: Option<Overdue>,
     /// The format to print the updated task with
     #[arg(short, long)]
     format: Option<Format>,
@@ -22,18 +27,44 @@ enum CompleteStatus {
     Incomplete,
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+enum Overdue {
+    /// Move on to the first occurrence that isn't overdue
+    SkipToNextFuture,
+    /// Move on to the next occurrence, even if it is overdue too
+    AdvanceOnce,
+}
+
 pub fn run(mut app: App, args: Args) -> Result<()> {
     let Args {
-        id,
+        id: query,
         complete,
+        overdue,
         format,
     } = args;
+    let Some(id) = cli_utils::find_task_id(app.tasks.values(), &query)? else {
+        println!("Task with id {} not found", query);
+        return Ok(());
+    };
     let complete_bool = match complete {
         CompleteStatus::Complete => true,
         CompleteStatus::Incomplete => false,
     };
 
-    let task_id = app.set_complete(id, complete_bool);
+    let catch_up = match (overdue, app.settings.overdue_repeats) {
+        (Some(overdue), _) => overdue == Overdue::SkipToNextFuture,
+        (None, OverdueRepeats::SkipToNextFuture) => true,
+        (None, OverdueRepeats::AdvanceOnce) => false,
+        (None, OverdueRepeats::Ask) => {
+            let missed = app
+                .get_task(id)
+                .map(|t| t.missed_occurrences())
+                .unwrap_or(0);
+            complete_bool && missed > 0 && ask_catch_up(missed, format)?
+        }
+    };
+
+    let task_id = app.set_complete(id, complete_bool, catch_up)?;
     match task_id {
         Some(task_id) => {
             let task = app.get_task(task_id).unwrap();
@@ -46,3 +77,23 @@ pub fn run(mut app: App, args: Args) -> Result<()> {
 
     Ok(())
 }
+
+/// Asks on the terminal. Scripts, which can't answer, have to pass `--overdue` instead
+fn ask_catch_up(missed: usize, format: Option<Format>) -> Result<bool> {
+    let json = matches!(format, Some(Format::Json | Format::JsonPretty));
+    if json || !io::stdin().is_terminal() {
+        return Err(anyhow!(
+            "The next {} occurrence(s) are overdue too. Pass --overdue skip-to-next-future \
+             or --overdue advance-once to choose what to do",
+            missed
+        ));
+    }
+    print!(
+        "The next {} occurrence(s) are overdue too. Skip them? [y/N] ",
+        missed
+    );
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
@@ -1,4 +1,4 @@
-use crate::{app::App, configuration::SettingsBuilder};
+use crate::{app::App, configuration::StorageBackend};
 use anyhow::Result;
 use clap::{Parser, ValueEnum};
 
@@ -16,6 +16,9 @@ pub struct Args {
     /// Set the icons
     #[clap(long)]
     icons: Option<Icons>,
+    /// Set where tasks are stored
+    #[clap(long)]
+    storage: Option<Storage>,
 }
 
 #[derive(Parser, Clone, Copy, ValueEnum)]
@@ -26,6 +29,14 @@ enum Icons {
     Chars,
 }
 
+#[derive(Parser, Clone, Copy, ValueEnum)]
+enum Storage {
+    /// Store tasks in tasks.json
+    Json,
+    /// Store tasks in a SQLite database (tasks.db). Existing tasks are imported the first time
+    Sqlite,
+}
+
 #[derive(Parser, Clone, Copy, ValueEnum)]
 enum Mode {
     /// Set the mode to vi
@@ -40,23 +51,28 @@ pub fn run(mut app: App, args: Args) -> Result<()> {
         show,
         mode,
         icons,
+        storage,
     } = args;
 
     if reset {
-        let mut sb = SettingsBuilder::default();
-        sb.save_to_file()?;
-        app.settings = sb.build();
+        app.settings.reset()?;
     }
 
     match mode {
-        Some(Mode::Vi) => app.settings.set_vi_mode(),
-        Some(Mode::Normal) => app.settings.set_normal_mode(),
+        Some(Mode::Vi) => app.settings.set_vi_mode()?,
+        Some(Mode::Normal) => app.settings.set_normal_mode()?,
         None => {}
     }
 
     match icons {
-        Some(Icons::Special) => app.settings.set_special_icons(),
-        Some(Icons::Chars) => app.settings.set_char_icons(),
+        Some(Icons::Special) => app.settings.set_special_icons()?,
+        Some(Icons::Chars) => app.settings.set_char_icons()?,
+        None => {}
+    }
+
+    match storage {
+        Some(Storage::Json) => app.settings.set_storage(StorageBackend::Json)?,
+        Some(Storage::Sqlite) => app.settings.set_storage(StorageBackend::Sqlite)?,
         None => {}
     }
 
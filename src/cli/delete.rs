@@ -5,22 +5,23 @@ use clap::Parser;
 
 #[derive(Parser)]
 pub struct Args {
-    /// The ID of the task to delete
+    /// The ID of the task to delete, or its series id (or a unique prefix of 4+ characters)
     #[arg(short, long)]
-    id: usize,
+    id: String,
     /// The format to print the deleted task with
     #[arg(short, long)]
     format: Option<Format>,
 }
 
 pub fn run(mut app: App, args: Args) -> Result<()> {
-    let Args { id, format } = args;
+    let Args { id: query, format } = args;
+    let Some(id) = cli_utils::find_task_id(app.tasks.values(), &query)? else {
+        println!("Task with id {} not found", query);
+        return Ok(());
+    };
     let task = app.get_task(id).cloned();
-    if task.is_none() {
-        println!("Task with id {} not found", id);
-    }
 
-    let task_id = app.delete_task(id);
+    let task_id = app.delete_task(id)?;
 
     match task_id {
         Some(_) => {
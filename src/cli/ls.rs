@@ -5,6 +5,7 @@ use crate::configuration::Settings;
 use crate::task::Task;
 use crate::utils;
 use anyhow::Result;
+use chrono::Days;
 use clap::{Parser, ValueEnum};
 
 use super::cli_utils;
@@ -18,21 +19,33 @@ pub struct Args {
     /// Whether to show complete tasks
     #[arg(short, long)]
     show_complete: bool,
+    /// Whether to show tasks that haven't started yet
+    #[arg(long)]
+    show_scheduled: bool,
+    /// Filter tasks by start date, e.g. "mon". Includes tasks that haven't started yet
+    #[arg(long)]
+    scheduled: Option<String>,
     /// Whether to show task descriptions
     #[arg(long)]
     show_descriptions: bool,
     /// Whether to show task urls
     #[arg(long)]
     show_urls: bool,
+    /// Whether to show the streaks and past completions of repeating tasks
+    #[arg(long)]
+    show_history: bool,
     /// Filter tasks by relative date
     #[arg(long)]
     date_filter: Option<DateFilter>,
-    /// Filter tasks by date
+    /// Filter tasks by due date, e.g. "24-12-2026" or "tomorrow"
     #[arg(long)]
     date: Option<String>,
     /// Filter by group
     #[arg(long)]
     group: Option<String>,
+    /// Show dates in the display format even if `humanize_dates` is set
+    #[arg(long)]
+    absolute_dates: bool,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
@@ -42,11 +55,16 @@ pub enum DateFilter {
     Past,
     TodayAndPast,
     Next24,
+    /// The week of today, starting on `first_day_of_week`
+    ThisWeek,
+    /// The week after this one
+    NextWeek,
 }
 
 pub fn filter_by_relative_date(
     tasks: HashMap<Id, Task>,
     date_filter: Option<DateFilter>,
+    settings: &Settings,
 ) -> HashMap<Id, Task> {
     let now = chrono::Local::now();
     match date_filter {
@@ -54,24 +72,38 @@ pub fn filter_by_relative_date(
             .into_iter()
             .filter(|(_, t)| {
                 let today = now.date_naive();
-                t.date.date_naive() == today
+                t.date.is_some_and(|d| d.date_naive() == today)
             })
             .collect(),
-        Some(DateFilter::Past) => tasks.into_iter().filter(|(_, t)| t.date < now).collect(),
+        Some(DateFilter::Past) => tasks
+            .into_iter()
+            .filter(|(_, t)| t.date.is_some_and(|d| d < now))
+            .collect(),
         Some(DateFilter::TodayAndPast) => tasks
             .into_iter()
             .filter(|(_, t)| {
                 let today = now.date_naive();
-                t.date.date_naive() <= today
+                t.date.is_some_and(|d| d.date_naive() <= today)
             })
             .collect(),
         Some(DateFilter::Next24) => tasks
             .into_iter()
             .filter(|(_, t)| {
                 let tomorrow = now + chrono::Duration::days(1);
-                t.date >= now && t.date < tomorrow
+                t.date.is_some_and(|d| d >= now && d < tomorrow)
             })
             .collect(),
+        Some(filter @ (DateFilter::ThisWeek | DateFilter::NextWeek)) => {
+            let mut start = utils::start_of_week(now.date_naive(), &settings.week_start());
+            if filter == DateFilter::NextWeek {
+                start = start + Days::new(7);
+            }
+            let week = start..start + Days::new(7);
+            tasks
+                .into_iter()
+                .filter(|(_, t)| t.date.is_some_and(|d| week.contains(&d.date_naive())))
+                .collect()
+        }
         _ => tasks,
     }
 }
@@ -83,12 +115,39 @@ pub fn filter_by_exact_date(
 ) -> Result<HashMap<Id, Task>> {
     let tasks = match date {
         Some(date) => {
-            let date = utils::parse_date(date.as_str(), settings)?;
+            let (date, has_time) = utils::parse_date(date.as_str(), settings)?;
+            tasks
+                .into_iter()
+                .filter(|(_, t)| match has_time {
+                    true => t.date == Some(date),
+                    // A day matches the tasks due at any time that day
+                    false => t.date.is_some_and(|d| d.date_naive() == date.date_naive()),
+                })
+                .collect()
+        }
+        None => tasks,
+    };
+    Ok(tasks)
+}
+
+pub fn filter_by_start_date(
+    tasks: HashMap<Id, Task>,
+    show_scheduled: bool,
+    start_date: Option<String>,
+    settings: &Settings,
+) -> Result<HashMap<Id, Task>> {
+    let tasks = match start_date {
+        Some(start_date) => {
+            let (start_date, _) = utils::parse_date(start_date.as_str(), settings)?;
             tasks
                 .into_iter()
-                .filter(|(_, t)| t.date == date)
+                .filter(|(_, t)| t.start_date == Some(start_date.date_naive()))
                 .collect()
         }
+        None if !show_scheduled => tasks
+            .into_iter()
+            .filter(|(_, t)| !t.is_scheduled())
+            .collect(),
         None => tasks,
     };
     Ok(tasks)
@@ -112,38 +171,46 @@ pub fn filter_by_group(tasks: HashMap<Id, Task>, group: Option<String>) -> HashM
     }
 }
 
-pub fn run(app: App, args: Args) -> Result<()> {
+pub fn run(mut app: App, args: Args) -> Result<()> {
     let Args {
         format,
         show_complete,
+        show_scheduled,
+        scheduled,
         show_descriptions,
         show_urls,
+        show_history,
         date_filter,
         date,
         group,
+        absolute_dates,
     } = args;
 
+    if absolute_dates {
+        app.settings.humanize_dates = false;
+    }
+
     let tasks: HashMap<Id, Task> = if !show_complete {
         app.tasks.into_iter().filter(|(_, t)| !t.complete).collect()
     } else {
         app.tasks
     };
 
-    let tasks = filter_by_relative_date(tasks, date_filter);
+    let tasks = filter_by_relative_date(tasks, date_filter, &app.settings);
     let tasks = filter_by_exact_date(tasks, date, &app.settings)?;
+    let tasks = filter_by_start_date(tasks, show_scheduled, scheduled, &app.settings)?;
     let tasks = filter_by_group(tasks, group);
 
     let mut tasks_vec = tasks.values().collect::<Vec<_>>();
 
-    tasks_vec.sort_by(|a, b| {
-        a.date.cmp(&b.date).then_with(|| a.name.cmp(&b.name))
-    });
+    tasks_vec.sort_by(|a, b| a.cmp_by_date(b).then_with(|| a.name.cmp(&b.name)));
 
     cli_utils::print_tasks(
         tasks_vec,
         format,
         show_descriptions,
         show_urls,
+        show_history,
         &app.settings,
     );
 
@@ -1,46 +1,69 @@
+use std::path::PathBuf;
+
+use crate::app::App;
 use anyhow::Result;
 use clap::Parser;
-use crate::app::App;
 
-mod ls;
 mod add;
-mod delete;
+mod cli_utils;
 mod complete;
 mod config;
-mod cli_utils;
+mod delete;
+mod ls;
+mod redo;
+mod skip;
+mod trash;
+mod undo;
 
 // Shared enums and structs
 mod formats;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
-struct Args {
+pub struct Args {
+    /// Use a different tasks database
+    #[arg(long, global = true, env = "TODUI_DB")]
+    pub db: Option<PathBuf>,
+    /// Use a different settings file
+    #[arg(long, global = true, env = "TODUI_CONFIG")]
+    pub config: Option<PathBuf>,
+    /// Runs the TUI when no command is given
     #[command(subcommand)]
-    command: Command,
+    pub command: Option<Command>,
 }
 
 #[derive(Parser)]
-enum Command {
+pub enum Command {
     /// Lists all the tasks
     Ls(ls::Args),
     /// Adds a task to your todos
     Add(add::Args),
-    /// Deletes a task from your todos
+    /// Moves a task from your todos to the trash
     Delete(delete::Args),
     /// Marks a task as complete or incomplete
     Complete(complete::Args),
+    /// Moves a repeating task on to its next occurrence without completing it
+    Skip(skip::Args),
     /// Sets default configurations
-    Config(config::Args)
+    Config(config::Args),
+    /// Reverts the last change to your todos
+    Undo(undo::Args),
+    /// Applies the last undone change again
+    Redo(redo::Args),
+    /// Lists, restores or empties the deleted tasks
+    Trash(trash::Args),
 }
 
-pub fn start_cli(app: App) -> Result<()> {
-    let args = Args::parse();
-
-    match args.command {
+pub fn start_cli(app: App, command: Command) -> Result<()> {
+    match command {
         Command::Ls(args) => ls::run(app, args),
         Command::Add(args) => add::run(app, args),
         Command::Delete(args) => delete::run(app, args),
         Command::Complete(args) => complete::run(app, args),
-        Command::Config(args) => config::run(app, args)
+        Command::Skip(args) => skip::run(app, args),
+        Command::Config(args) => config::run(app, args),
+        Command::Undo(args) => undo::run(app, args),
+        Command::Redo(args) => redo::run(app, args),
+        Command::Trash(args) => trash::run(app, args),
     }
 }
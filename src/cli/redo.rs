@@ -0,0 +1,15 @@
+use crate::app::App;
+use anyhow::Result;
+use clap::Parser;
+
+#[derive(Parser)]
+pub struct Args {}
+
+pub fn run(mut app: App, _args: Args) -> Result<()> {
+    match app.redo()? {
+        Some(operation) => println!("Redid {}", operation),
+        None => println!("Nothing to redo"),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,32 @@
+use super::{cli_utils, formats::Format};
+use crate::app::App;
+use anyhow::Result;
+use clap::Parser;
+
+#[derive(Parser)]
+pub struct Args {
+    /// The ID of the repeating task to skip, or its series id (or a unique prefix of 4+ characters)
+    #[arg(short, long)]
+    id: String,
+    /// The format to print the updated task with
+    #[arg(short, long)]
+    format: Option<Format>,
+}
+
+pub fn run(mut app: App, args: Args) -> Result<()> {
+    let Args { id: query, format } = args;
+    let Some(id) = cli_utils::find_task_id(app.tasks.values(), &query)? else {
+        println!("Task with id {} not found", query);
+        return Ok(());
+    };
+
+    match app.skip_occurrence(id)? {
+        Some(task_id) => {
+            let task = app.get_task(task_id).unwrap();
+            cli_utils::print_task(task, format, &app.settings);
+        }
+        None => println!("Task with id {} has no next occurrence to skip to", id),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use super::{cli_utils, formats::Format};
+use crate::{app::App, trash::TrashedTask, utils};
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+#[derive(Parser)]
+pub struct Args {
+    #[command(subcommand)]
+    command: TrashCommand,
+}
+
+#[derive(Subcommand)]
+enum TrashCommand {
+    /// Lists the deleted tasks, most recently deleted first
+    Ls {
+        /// The format to print the deleted tasks with
+        #[arg(long)]
+        format: Option<Format>,
+    },
+    /// Moves a deleted task back to your todos
+    Restore {
+        /// The ID the task had when it was deleted, or its series id (or a unique prefix of 4+ characters)
+        #[arg(short, long)]
+        id: String,
+        /// The format to print the restored task with
+        #[arg(short, long)]
+        format: Option<Format>,
+    },
+    /// Deletes the tasks in the trash forever
+    Empty,
+}
+
+pub fn run(mut app: App, args: Args) -> Result<()> {
+    match args.command {
+        TrashCommand::Ls { format } => {
+            let trashed = app.trashed_tasks()?;
+            print_trashed_tasks(&trashed, format, &app);
+        }
+        TrashCommand::Restore { id: query, format } => {
+            let trashed = app.trashed_tasks()?;
+            let restored = match cli_utils::find_task_id(trashed.iter().map(|t| &t.task), &query)? {
+                Some(id) => app.restore_task(id)?,
+                None => None,
+            };
+            match restored {
+                Some(task_id) => {
+                    let task = app.get_task(task_id).unwrap();
+                    cli_utils::print_task(task, format, &app.settings);
+                }
+                None => println!("Task with id {} not found in the trash", query),
+            }
+        }
+        TrashCommand::Empty => {
+            let count = app.empty_trash()?;
+            println!("Deleted {} task(s) forever", count);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_trashed_tasks(trashed: &[TrashedTask], format: Option<Format>, app: &App) {
+    match format {
+        Some(Format::Json) => println!(
+            "{}",
+            serde_json::to_string(trashed).expect("Failed to serialize tasks to JSON")
+        ),
+        Some(Format::JsonPretty) => println!(
+            "{}",
+            serde_json::to_string_pretty(trashed).expect("Failed to serialize tasks to JSON")
+        ),
+        _ => {
+            let names: Vec<String> = trashed
+                .iter()
+                .map(|t| format!("{} ({})", t.task.name, t.task.id.unwrap()))
+                .collect();
+            let longest_name = names.iter().map(|n| n.len()).max().unwrap_or(0);
+
+            println!("{:width$}  Deleted", "Name", width = longest_name);
+            for (name, trashed) in names.iter().zip(trashed) {
+                let deleted_at =
+                    utils::date_to_display_str(&trashed.deleted_at, true, &app.settings);
+                println!("{:width$}  {}", name, deleted_at, width = longest_name);
+            }
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use crate::app::App;
+use anyhow::Result;
+use clap::Parser;
+
+#[derive(Parser)]
+pub struct Args {}
+
+pub fn run(mut app: App, _args: Args) -> Result<()> {
+    match app.undo()? {
+        Some(operation) => println!("Undid {}", operation),
+        None => println!("Nothing to undo"),
+    }
+
+    Ok(())
+}
@@ -1,13 +1,14 @@
-use crate::utils;
-use anyhow::{anyhow, Result};
+use crate::day_of_week::DayOfWeek;
+use crate::error::StorageError;
+use crate::locale::Locale;
+use crate::utils::{self, FileStamp};
 use crossterm::event::KeyCode;
 use serde::{Deserialize, Serialize};
-use std::fs;
-use std::fs::OpenOptions;
-use std::io::prelude::*;
-use std::path::{Path, PathBuf};
+use std::io;
+use std::path::PathBuf;
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
 pub struct Icons {
     pub complete: String,
     pub incomplete: String,
@@ -189,6 +190,7 @@ where
     key.serialize(serializer)
 }
 #[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
 pub struct KeyBindings {
     #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
     pub quit: KeyCode,
@@ -220,6 +222,20 @@ pub struct KeyBindings {
     pub next_group: KeyCode,
     #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
     pub prev_group: KeyCode,
+    #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
+    pub undo: KeyCode,
+    #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
+    pub redo: KeyCode,
+    #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
+    pub open_trash: KeyCode,
+    #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
+    pub restore_task: KeyCode,
+    #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
+    pub empty_trash: KeyCode,
+    #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
+    pub skip_occurrence: KeyCode,
+    #[serde(deserialize_with = "deserialize_key", serialize_with = "serialize_key")]
+    pub toggle_scheduled_tasks: KeyCode,
 }
 
 impl KeyBindings {
@@ -274,6 +290,13 @@ impl KeyBindings {
             open_link: KeyCode::Enter,
             next_group: KeyCode::Char('l'),
             prev_group: KeyCode::Char('h'),
+            undo: KeyCode::Char('u'),
+            redo: KeyCode::Char('r'),
+            open_trash: KeyCode::Char('t'),
+            restore_task: KeyCode::Enter,
+            empty_trash: KeyCode::Char('x'),
+            skip_occurrence: KeyCode::Char('s'),
+            toggle_scheduled_tasks: KeyCode::Char('p'),
         }
     }
 }
@@ -296,11 +319,19 @@ impl Default for KeyBindings {
             open_link: KeyCode::Enter,
             next_group: KeyCode::Right,
             prev_group: KeyCode::Left,
+            undo: KeyCode::Char('u'),
+            redo: KeyCode::Char('r'),
+            open_trash: KeyCode::Char('t'),
+            restore_task: KeyCode::Enter,
+            empty_trash: KeyCode::Char('x'),
+            skip_occurrence: KeyCode::Char('s'),
+            toggle_scheduled_tasks: KeyCode::Char('p'),
         }
     }
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
 pub struct DateFormats {
     pub display_date_format: String,
     pub display_datetime_format: String,
@@ -310,8 +341,8 @@ pub struct DateFormats {
     pub input_datetime_hint: String,
 }
 
-impl DateFormats {
-    fn new() -> Self {
+impl Default for DateFormats {
+    fn default() -> Self {
         DateFormats {
             display_date_format: "%a %b %-d".to_string(),
             display_datetime_format: "%a %b %-d at %-H:%M".to_string(),
@@ -324,6 +355,7 @@ impl DateFormats {
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
 pub struct Colors {
     #[serde(
         serialize_with = "serialize_color",
@@ -342,7 +374,7 @@ pub struct Colors {
     pub accent_color: tui::style::Color,
 }
 
-impl Colors {
+impl Default for Colors {
     fn default() -> Self {
         Colors {
             primary_color: tui::style::Color::LightGreen,
@@ -352,148 +384,333 @@ impl Colors {
     }
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub enum StorageBackend {
+    /// A single `tasks.json` file
+    #[default]
+    Json,
+    /// A `tasks.db` SQLite database. Requires the `sqlite` feature
+    Sqlite,
+}
+
+/// What completing a repeating task does when more of its occurrences are overdue
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OverdueRepeats {
+    /// Moves on to the first occurrence that isn't overdue
+    SkipToNextFuture,
+    /// Moves on to the next occurrence, even if it is overdue too
+    #[default]
+    AdvanceOnce,
+    /// Asks which of the two to do
+    Ask,
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
 pub struct Settings {
     pub date_formats: DateFormats,
     pub show_complete: bool,
+    /// Whether to show the tasks that haven't started yet
+    pub show_scheduled: bool,
+    /// Whether to show the due time of tasks given in a time zone there as well
+    pub show_timezones: bool,
+    /// Whether to show due dates relative to today, like "tomorrow" or "2 days overdue"
+    pub humanize_dates: bool,
+    /// The language of month and weekday names
+    pub locale: Locale,
+    /// The day weeks start on in the `ls` week filters. Defaults to the usual one in
+    /// `locale`. Repeats every few weeks count weeks from Monday regardless, like RRULEs
+    /// without `WKST`.
+    pub first_day_of_week: Option<DayOfWeek>,
     pub current_group: Option<String>,
     pub icons: Icons,
     pub colors: Colors,
     pub keybindings: KeyBindings,
+    pub storage: StorageBackend,
+    /// How many operations can be undone
+    pub history_size: usize,
+    /// How many days deleted tasks are kept in the trash
+    pub trash_retention_days: u32,
+    /// How many past completions of a repeating task are kept. Older ones only count
+    /// towards its streaks
+    pub completion_history: usize,
+    /// What completing an overdue repeating task does
+    pub overdue_repeats: OverdueRepeats,
+    /// Where these settings were loaded from
+    #[serde(skip)]
+    pub file: PathBuf,
+    /// The version of `file` these settings match
+    #[serde(skip)]
+    stamp: Option<FileStamp>,
 }
 
-impl Settings {
-    pub fn set_show_complete(&mut self, show_complete: bool) {
-        self.show_complete = show_complete;
-        self.save_state();
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            show_complete: true,
+            show_scheduled: false,
+            show_timezones: false,
+            humanize_dates: false,
+            locale: Locale::default(),
+            first_day_of_week: None,
+            current_group: None,
+            icons: Icons::default(),
+            date_formats: DateFormats::default(),
+            colors: Colors::default(),
+            keybindings: KeyBindings::default(),
+            storage: StorageBackend::default(),
+            history_size: 100,
+            trash_retention_days: 30,
+            completion_history: 100,
+            overdue_repeats: OverdueRepeats::default(),
+            file: PathBuf::new(),
+            stamp: None,
+        }
     }
+}
 
-    pub fn set_current_group(&mut self, group: Option<String>) {
-        self.current_group = group;
-        self.save_state();
+impl Settings {
+    pub fn set_show_complete(&mut self, show_complete: bool) -> Result<(), StorageError> {
+        self.modify(|s| s.show_complete = show_complete)
     }
 
-    pub fn set_vi_mode(&mut self) {
-        self.keybindings = KeyBindings::get_vi_default();
-        self.save_state()
+    /// The day weeks start on
+    pub fn week_start(&self) -> DayOfWeek {
+        self.first_day_of_week
+            .clone()
+            .unwrap_or_else(|| self.locale.week_start())
     }
 
-    pub fn set_normal_mode(&mut self) {
-        self.keybindings = KeyBindings::default();
-        self.save_state()
+    pub fn set_show_scheduled(&mut self, show_scheduled: bool) -> Result<(), StorageError> {
+        self.modify(|s| s.show_scheduled = show_scheduled)
     }
 
-    pub fn set_special_icons(&mut self) {
-        self.icons = Icons::special();
-        self.save_state()
+    pub fn set_current_group(&mut self, group: Option<String>) -> Result<(), StorageError> {
+        self.modify(|s| s.current_group = group)
     }
 
-    pub fn set_char_icons(&mut self) {
-        self.icons = Icons::default();
-        self.save_state()
+    pub fn set_vi_mode(&mut self) -> Result<(), StorageError> {
+        self.modify(|s| s.keybindings = KeyBindings::get_vi_default())
     }
 
-    pub fn save_state(&self) {
-        let settings_path =
-            SettingsBuilder::get_settings_path().expect("Settings file should exist.");
-        utils::save_settings(&settings_path, self);
+    pub fn set_normal_mode(&mut self) -> Result<(), StorageError> {
+        self.modify(|s| s.keybindings = KeyBindings::default())
     }
-}
 
-#[derive(Deserialize, Serialize, Debug)]
-pub struct SettingsBuilder {
-    pub date_formats: DateFormats,
-    pub show_complete: bool,
-    pub current_group: Option<String>,
-    pub icons: Icons,
-    pub colors: Colors,
-    pub keybindings: KeyBindings,
-}
+    pub fn set_special_icons(&mut self) -> Result<(), StorageError> {
+        self.modify(|s| s.icons = Icons::special())
+    }
 
-impl SettingsBuilder {
-    pub fn default_path() -> Result<PathBuf> {
-        match dirs::home_dir() {
-            Some(path) => {
-                let path = Path::new(&path);
+    pub fn set_char_icons(&mut self) -> Result<(), StorageError> {
+        self.modify(|s| s.icons = Icons::default())
+    }
 
-                let config_dir = path.join(".config");
-                if !path.exists() {
-                    fs::create_dir_all(path)?;
-                }
+    pub fn set_storage(&mut self, storage: StorageBackend) -> Result<(), StorageError> {
+        self.modify(|s| s.storage = storage)
+    }
 
-                let todo_dir = config_dir.join("todui");
-                if !todo_dir.exists() {
-                    fs::create_dir_all(&todo_dir)?;
-                }
-                Ok(todo_dir)
+    /// Restores the default settings, keeping the file they are saved to
+    pub fn reset(&mut self) -> Result<(), StorageError> {
+        self.modify(|s| {
+            *s = Settings {
+                file: s.file.clone(),
+                ..Settings::default()
             }
-            None => Err(anyhow!("Could not find home directory")),
-        }
+        })
     }
 
-    pub fn get_default_db_file() -> Result<PathBuf> {
-        let default_path = Self::default_path()?;
-        let path = default_path.join("tasks.json");
+    /// Applies `change` to the latest version of the settings and saves them, so that
+    /// edits made to the file since it was read aren't overwritten
+    fn modify(&mut self, change: impl FnOnce(&mut Settings)) -> Result<(), StorageError> {
+        let _lock = utils::lock_file(&self.file, true)?;
+        let stamp = utils::file_stamp(&self.file)?;
+        if stamp.is_some() && stamp != self.stamp {
+            *self = Settings {
+                file: self.file.clone(),
+                ..utils::load_settings_locked(&self.file)?
+            };
+        }
+        change(self);
+        self.stamp = utils::save_settings_locked(&self.file, self)?;
+        Ok(())
+    }
 
-        if !path.exists() {
-            let mut file = OpenOptions::new().write(true).create(true).open(&path)?;
-            writeln!(file, "{{}}")?;
+    /// Reloads the settings if their file was changed by someone else. Returns whether it did
+    pub fn reload_if_changed(&mut self) -> Result<bool, StorageError> {
+        let stamp = utils::file_stamp(&self.file)?;
+        if stamp.is_none() || stamp == self.stamp {
+            return Ok(false);
         }
 
-        Ok(path)
+        *self = Settings {
+            file: self.file.clone(),
+            stamp,
+            ..utils::load_settings(&self.file)?
+        };
+        Ok(true)
     }
+}
 
-    pub fn get_settings_path() -> Result<PathBuf> {
-        let default_path = Self::default_path()?;
-        let path = default_path.join("settings.json");
-        if !path.exists() {
-            Self::default().save_to_file()?;
-        }
-        Ok(path)
+/// Where todui reads and writes its files
+#[derive(Debug, Clone)]
+pub struct Paths {
+    /// Holds the database when it wasn't given a custom location
+    pub dir: Option<PathBuf>,
+    pub config_file: PathBuf,
+    /// Only set when the database location was overridden
+    pub db_file: Option<PathBuf>,
+}
+
+impl Paths {
+    /// Custom locations, from the command line or the environment, take precedence over the
+    /// files in the default directory
+    pub fn resolve(
+        config_file: Option<PathBuf>,
+        db_file: Option<PathBuf>,
+    ) -> Result<Paths, StorageError> {
+        // Only look for the home directory if some file needs it
+        let config_file = match config_file {
+            Some(file) => file,
+            None => Self::default_dir()?.join("settings.json"),
+        };
+        let dir = match db_file {
+            Some(_) => None,
+            None => Some(Self::default_dir()?),
+        };
+        Ok(Paths {
+            dir,
+            config_file,
+            db_file,
+        })
     }
 
-    pub fn save_to_file(&self) -> Result<()> {
-        let default_path = Self::default_path()?;
-        let path = default_path.join("settings.json");
-        let settings_json = serde_json::to_string_pretty(&self)?;
-        fs::write(&path, settings_json)?;
-        Ok(())
+    /// `$XDG_CONFIG_HOME/todui`, falling back to `~/.config/todui`
+    pub fn default_dir() -> Result<PathBuf, StorageError> {
+        Self::config_dir(
+            std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from),
+            dirs::home_dir(),
+        )
     }
 
-    pub fn build(&mut self) -> Settings {
-        Settings {
-            date_formats: self.date_formats.clone(),
-            show_complete: self.show_complete,
-            current_group: self.current_group.clone(),
-            icons: self.icons.clone(),
-            colors: self.colors.clone(),
-            keybindings: self.keybindings.clone(),
+    fn config_dir(
+        xdg_config_home: Option<PathBuf>,
+        home: Option<PathBuf>,
+    ) -> Result<PathBuf, StorageError> {
+        let legacy_dir = home.map(|home| home.join(".config").join("todui"));
+        match (xdg_config_home, legacy_dir) {
+            // Relative paths are invalid according to the XDG spec and should be ignored
+            (Some(path), legacy_dir) if path.is_absolute() => {
+                let dir = path.join("todui");
+                // Older versions always used ~/.config/todui, so keep using the files there
+                // until the XDG directory is created
+                match legacy_dir {
+                    Some(legacy_dir) if !dir.exists() && legacy_dir.exists() => Ok(legacy_dir),
+                    _ => Ok(dir),
+                }
+            }
+            (_, Some(legacy_dir)) => Ok(legacy_dir),
+            (_, None) => Err(StorageError::Io {
+                path: PathBuf::from("~/.config/todui"),
+                source: io::Error::new(io::ErrorKind::NotFound, "Could not find home directory"),
+            }),
         }
     }
-}
 
-impl Default for SettingsBuilder {
-    fn default() -> Self {
-        SettingsBuilder {
-            show_complete: true,
-            current_group: None,
-            icons: Icons::default(),
-            date_formats: DateFormats::new(),
-            colors: Colors::default(),
-            keybindings: KeyBindings::default(),
+    pub fn db_file(&self, backend: &StorageBackend) -> PathBuf {
+        match (&self.db_file, &self.dir, backend) {
+            (Some(path), _, _) => path.clone(),
+            (None, Some(dir), StorageBackend::Json) => dir.join("tasks.json"),
+            (None, Some(dir), StorageBackend::Sqlite) => dir.join("tasks.db"),
+            (None, None, _) => unreachable!("`resolve` sets the directory without a database"),
         }
     }
 }
 
-pub fn get_configuration() -> Settings {
-    let settings_path = SettingsBuilder::get_settings_path().expect("Settings file should exist.");
-    let file = OpenOptions::new()
-        .read(true)
-        .open(settings_path)
-        .expect("Could not open settings file");
-    serde_json::from_reader(file).expect("Could not parse settings file")
+/// Loads the settings, creating the file with the defaults if it doesn't exist yet
+pub fn get_configuration(paths: &Paths) -> Result<Settings, StorageError> {
+    let file = &paths.config_file;
+    if !file.exists() {
+        utils::ensure_parent_dir(file)?;
+        utils::save_settings(file, &Settings::default())?;
+    }
+
+    let mut settings = utils::load_settings(file)?;
+    settings.file = file.clone();
+    settings.stamp = utils::file_stamp(file)?;
+    Ok(settings)
 }
 
-pub fn get_db_file() -> PathBuf {
-    SettingsBuilder::get_default_db_file().expect("Could not find default db file")
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn settings_changes_from_other_writers_are_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = Paths {
+            dir: Some(dir.path().to_path_buf()),
+            config_file: dir.path().join("settings.json"),
+            db_file: None,
+        };
+        let mut first = get_configuration(&paths).unwrap();
+        let mut second = get_configuration(&paths).unwrap();
+
+        first.set_show_complete(false).unwrap();
+        second.set_vi_mode().unwrap();
+        assert!(!second.show_complete);
+
+        let settings = get_configuration(&paths).unwrap();
+        assert!(!settings.show_complete);
+        assert_eq!(settings.keybindings.up, KeyBindings::get_vi_default().up);
+    }
+
+    #[test]
+    fn custom_files_dont_need_a_home() {
+        let paths = Paths::resolve(Some("/settings.json".into()), Some("/tasks.db".into()));
+        let paths = paths.unwrap();
+        assert_eq!(paths.dir, None);
+        assert_eq!(
+            paths.db_file(&StorageBackend::Sqlite),
+            PathBuf::from("/tasks.db")
+        );
+    }
+
+    #[test]
+    fn config_dir_follows_xdg() {
+        let home = tempfile::tempdir().unwrap();
+        let xdg = home.path().join("xdg");
+        let dir = Paths::config_dir(Some(xdg.clone()), Some(home.path().to_path_buf()));
+        assert_eq!(dir.unwrap(), xdg.join("todui"));
+    }
+
+    #[test]
+    fn config_dir_ignores_relative_xdg() {
+        let home = tempfile::tempdir().unwrap();
+        let dir = Paths::config_dir(Some("xdg".into()), Some(home.path().to_path_buf()));
+        assert_eq!(dir.unwrap(), home.path().join(".config").join("todui"));
+    }
+
+    #[test]
+    fn config_dir_keeps_using_the_legacy_dir() {
+        let home = tempfile::tempdir().unwrap();
+        let xdg = home.path().join("xdg");
+        let legacy_dir = home.path().join(".config").join("todui");
+        fs::create_dir_all(&legacy_dir).unwrap();
+        let dir = Paths::config_dir(Some(xdg.clone()), Some(home.path().to_path_buf()));
+        assert_eq!(dir.unwrap(), legacy_dir);
+
+        // Once the XDG directory exists, it wins
+        fs::create_dir_all(xdg.join("todui")).unwrap();
+        let dir = Paths::config_dir(Some(xdg.clone()), Some(home.path().to_path_buf()));
+        assert_eq!(dir.unwrap(), xdg.join("todui"));
+    }
+
+    #[test]
+    fn config_dir_needs_a_home_without_xdg() {
+        assert!(Paths::config_dir(None, None).is_err());
+        let dir = Paths::config_dir(Some("/xdg".into()), None);
+        assert_eq!(dir.unwrap(), PathBuf::from("/xdg/todui"));
+    }
 }
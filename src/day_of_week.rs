@@ -5,7 +5,7 @@ use std::{
     str::FromStr,
 };
 
-#[derive(Serialize, Deserialize, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
 pub enum DayOfWeek {
     Monday,
     Tuesday,
@@ -29,6 +29,19 @@ impl DayOfWeek {
         }
     }
 
+    /// The inverse of `to_int`, wrapping around after Sunday
+    pub fn from_int(n: u32) -> Self {
+        match (n + 6) % 7 {
+            0 => DayOfWeek::Monday,
+            1 => DayOfWeek::Tuesday,
+            2 => DayOfWeek::Wednesday,
+            3 => DayOfWeek::Thursday,
+            4 => DayOfWeek::Friday,
+            5 => DayOfWeek::Saturday,
+            _ => DayOfWeek::Sunday,
+        }
+    }
+
     pub fn from_chrono(day: chrono::Weekday) -> Self {
         match day {
             chrono::Weekday::Mon => Self::Monday,
@@ -47,13 +60,13 @@ impl FromStr for DayOfWeek {
 
     fn from_str(s: &str) -> Result<DayOfWeek, Self::Err> {
         match s.to_lowercase().as_str() {
-            "mon" => Ok(DayOfWeek::Monday),
-            "tue" => Ok(DayOfWeek::Tuesday),
-            "wed" => Ok(DayOfWeek::Wednesday),
-            "thu" => Ok(DayOfWeek::Thursday),
-            "fri" => Ok(DayOfWeek::Friday),
-            "sat" => Ok(DayOfWeek::Saturday),
-            "sun" => Ok(DayOfWeek::Sunday),
+            "mon" | "monday" => Ok(DayOfWeek::Monday),
+            "tue" | "tuesday" => Ok(DayOfWeek::Tuesday),
+            "wed" | "wednesday" => Ok(DayOfWeek::Wednesday),
+            "thu" | "thursday" => Ok(DayOfWeek::Thursday),
+            "fri" | "friday" => Ok(DayOfWeek::Friday),
+            "sat" | "saturday" => Ok(DayOfWeek::Saturday),
+            "sun" | "sunday" => Ok(DayOfWeek::Sunday),
             _ => Err(anyhow::anyhow!("Invalid day of the week")),
         }
     }
@@ -0,0 +1,156 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::app::Id;
+
+#[derive(Debug)]
+pub enum StorageError {
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        task_id: Option<Id>,
+        message: String,
+    },
+    SchemaVersion {
+        path: PathBuf,
+        found: u32,
+        supported: u32,
+    },
+    Database {
+        path: PathBuf,
+        message: String,
+    },
+    /// An undo or redo found the task in a different state than the operation left it in
+    Conflict {
+        path: PathBuf,
+        id: Id,
+    },
+}
+
+impl StorageError {
+    /// Builds a `map_err` adapter that attaches `path` to an io error
+    pub fn io(path: &Path) -> impl FnOnce(io::Error) -> StorageError + '_ {
+        move |source| StorageError::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    pub fn parse(path: &Path, error: &serde_json::Error, task_id: Option<Id>) -> StorageError {
+        // serde_json appends the position to its message, but we already print it
+        let position = format!(" at line {} column {}", error.line(), error.column());
+        let message = error.to_string();
+        let message = message.strip_suffix(&position).unwrap_or(&message);
+
+        StorageError::Parse {
+            path: path.to_path_buf(),
+            line: error.line(),
+            column: error.column(),
+            task_id,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            StorageError::Io { path, .. } => path,
+            StorageError::Parse { path, .. } => path,
+            StorageError::SchemaVersion { path, .. } => path,
+            StorageError::Database { path, .. } => path,
+            StorageError::Conflict { path, .. } => path,
+        }
+    }
+
+    /// A suggestion on how to get out of this error, shown below the error itself
+    pub fn hint(&self) -> String {
+        let path = self.path().display();
+        match self {
+            StorageError::Io { source, .. } if source.kind() == io::ErrorKind::PermissionDenied => {
+                format!("Check that you have read and write permissions on {}", path)
+            }
+            StorageError::Io { .. } => {
+                format!("Check that {} exists and that its directory is writable", path)
+            }
+            StorageError::Parse { .. } => format!(
+                "Fix the entry by hand, restore the previous version from {}.bak, or delete {} to start over",
+                path, path
+            ),
+            StorageError::SchemaVersion { .. } => {
+                "This file was written by a newer version of todui. Upgrade todui to open it".to_string()
+            }
+            StorageError::Database { .. } => format!(
+                "Check that {} is a todui database, or switch back with `todui config --storage json`",
+                path
+            ),
+            StorageError::Conflict { .. } => format!(
+                "The operation was dropped from the history in {}. Run the command again to move past it",
+                path
+            ),
+        }
+    }
+}
+
+impl Display for StorageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageError::Io { path, source } => {
+                write!(f, "Unable to access {}: {}", path.display(), source)
+            }
+            StorageError::Parse {
+                path,
+                line,
+                column,
+                task_id,
+                message,
+            } => {
+                write!(
+                    f,
+                    "Unable to parse {} at line {}, column {}",
+                    path.display(),
+                    line,
+                    column
+                )?;
+                if let Some(id) = task_id {
+                    write!(f, " (task {})", id)?;
+                }
+                write!(f, ": {}", message)
+            }
+            StorageError::SchemaVersion {
+                path,
+                found,
+                supported,
+            } => write!(
+                f,
+                "{} has schema version {}, but this version of todui only supports up to {}",
+                path.display(),
+                found,
+                supported
+            ),
+            StorageError::Database { path, message } => {
+                write!(f, "Database error in {}: {}", path.display(), message)
+            }
+            StorageError::Conflict { id, .. } => {
+                write!(
+                    f,
+                    "Task {} was changed since, so the operation can't be reverted",
+                    id
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for StorageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StorageError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::Id,
+    error::StorageError,
+    task::Task,
+    utils::{self, FileStamp},
+};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OperationKind {
+    Add,
+    Delete,
+    Edit,
+    Complete,
+    Uncomplete,
+    Restore,
+    Skip,
+}
+
+impl Display for OperationKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            OperationKind::Add => "add",
+            OperationKind::Delete => "delete",
+            OperationKind::Edit => "edit",
+            OperationKind::Complete => "complete",
+            OperationKind::Uncomplete => "uncomplete",
+            OperationKind::Restore => "restore",
+            OperationKind::Skip => "skip",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// A change to a single task. `before` is `None` for added tasks and `after` is `None`
+/// for deleted ones.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Operation {
+    pub kind: OperationKind,
+    pub id: Id,
+    pub before: Option<Task>,
+    pub after: Option<Task>,
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = self
+            .after
+            .as_ref()
+            .or(self.before.as_ref())
+            .map(|t| t.name.as_str())
+            .unwrap_or_default();
+        write!(f, "{} '{}' ({})", self.kind, name, self.id)
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct Stacks {
+    undo: Vec<Operation>,
+    redo: Vec<Operation>,
+}
+
+/// The operations that can be undone and redone. Unless it's kept in memory, the
+/// history is saved next to the tasks so that it outlives the session, and is re-read
+/// whenever another process changed it.
+pub struct History {
+    stacks: Stacks,
+    /// How many operations can be undone
+    limit: usize,
+    file: Option<PathBuf>,
+    stamp: Option<FileStamp>,
+}
+
+impl History {
+    pub fn in_memory(limit: usize) -> History {
+        History {
+            stacks: Stacks::default(),
+            limit,
+            file: None,
+            stamp: None,
+        }
+    }
+
+    pub fn open(file: PathBuf, limit: usize) -> Result<History, StorageError> {
+        let _lock = utils::lock_file(&file, false)?;
+        let mut history = History {
+            file: Some(file),
+            ..History::in_memory(limit)
+        };
+        history.sync()?;
+        Ok(history)
+    }
+
+    /// Takes the exclusive lock on the history file, released when the returned handle
+    /// is dropped. `pop_undo`, `pop_redo`, `push_undo`, `push_redo` and `save` expect it
+    /// to be held, so that operations recorded by another process in between aren't
+    /// overwritten.
+    pub fn lock(&self) -> Result<Option<fs::File>, StorageError> {
+        self.file
+            .as_deref()
+            .map(|file| utils::lock_file(file, true))
+            .transpose()
+    }
+
+    /// Adds a new operation. Anything that was undone can't be redone after this
+    pub fn record(&mut self, operation: Operation) -> Result<(), StorageError> {
+        let _lock = self.lock()?;
+        self.sync()?;
+        self.stacks.undo.push(operation);
+        self.stacks.redo.clear();
+        self.save()
+    }
+
+    pub fn pop_undo(&mut self) -> Result<Option<Operation>, StorageError> {
+        self.sync()?;
+        Ok(self.stacks.undo.pop())
+    }
+
+    pub fn pop_redo(&mut self) -> Result<Option<Operation>, StorageError> {
+        self.sync()?;
+        Ok(self.stacks.redo.pop())
+    }
+
+    pub fn push_undo(&mut self, operation: Operation) -> Result<(), StorageError> {
+        self.stacks.undo.push(operation);
+        self.save()
+    }
+
+    pub fn push_redo(&mut self, operation: Operation) -> Result<(), StorageError> {
+        self.stacks.redo.push(operation);
+        self.save()
+    }
+
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// Writes the history to its file, dropping the oldest operations over the limit
+    pub fn save(&mut self) -> Result<(), StorageError> {
+        if self.stacks.undo.len() > self.limit {
+            self.stacks
+                .undo
+                .drain(..self.stacks.undo.len() - self.limit);
+        }
+        if self.stacks.redo.len() > self.limit {
+            self.stacks
+                .redo
+                .drain(..self.stacks.redo.len() - self.limit);
+        }
+
+        if let Some(file) = &self.file {
+            self.stamp = utils::write_locked(file, &self.stacks)?;
+        }
+        Ok(())
+    }
+
+    /// Re-reads the history if another process changed it. Expects the lock to be held.
+    fn sync(&mut self) -> Result<(), StorageError> {
+        if let Some(file) = &self.file {
+            if let Some(stacks) = utils::read_locked(file, &mut self.stamp)? {
+                self.stacks = stacks;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{thread, time::Duration};
+
+    fn added(id: Id) -> Operation {
+        Operation {
+            kind: OperationKind::Add,
+            id,
+            before: None,
+            after: Some(Task::default()),
+        }
+    }
+
+    fn ids(operations: &[Operation]) -> Vec<Id> {
+        operations.iter().map(|o| o.id).collect()
+    }
+
+    #[test]
+    fn operations_from_other_processes_are_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("tasks.json.history");
+        let mut first = History::open(file.clone(), 10).unwrap();
+        let mut second = History::open(file.clone(), 10).unwrap();
+
+        first.record(added(1)).unwrap();
+        second.record(added(2)).unwrap();
+        {
+            let _lock = first.lock().unwrap();
+            let operation = first.pop_undo().unwrap().unwrap();
+            assert_eq!(operation.id, 2);
+            first.push_redo(operation).unwrap();
+        }
+
+        let history = History::open(file, 10).unwrap();
+        assert_eq!(ids(&history.stacks.undo), [1]);
+        assert_eq!(ids(&history.stacks.redo), [2]);
+    }
+
+    #[test]
+    fn recording_waits_for_an_undo_in_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("tasks.json.history");
+        let mut history = History::open(file.clone(), 10).unwrap();
+        history.record(added(1)).unwrap();
+
+        let lock = history.lock().unwrap();
+        let operation = history.pop_undo().unwrap().unwrap();
+        let other = {
+            let file = file.clone();
+            thread::spawn(move || History::open(file, 10)?.record(added(2)))
+        };
+        // Give the other process time to try recording in the middle of the undo
+        thread::sleep(Duration::from_millis(100));
+        history.push_redo(operation).unwrap();
+        drop(lock);
+        other.join().unwrap().unwrap();
+
+        let history = History::open(file, 10).unwrap();
+        assert_eq!(ids(&history.stacks.undo), [2]);
+        // Recording a new operation clears what could be redone
+        assert!(history.stacks.redo.is_empty());
+    }
+}
@@ -3,9 +3,17 @@ pub mod cli;
 pub mod ui;
 
 pub mod day_of_week;
+pub mod locale;
+pub mod natural_date;
 pub mod repeat;
+pub mod rrule;
 pub mod task;
 pub mod task_form;
 
 pub mod configuration;
+pub mod error;
+pub mod history;
+pub mod migrations;
+pub mod storage;
+pub mod trash;
 pub mod utils;
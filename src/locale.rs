@@ -0,0 +1,489 @@
+use crate::day_of_week::DayOfWeek;
+use chrono::{DateTime, Datelike, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// The language month and weekday names are shown and typed in. Names in English are
+/// understood in every locale.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    #[serde(rename = "en")]
+    English,
+    /// English, with weeks starting on Sunday
+    #[serde(rename = "en-US")]
+    AmericanEnglish,
+    #[serde(rename = "es")]
+    Spanish,
+    #[serde(rename = "fr")]
+    French,
+    #[serde(rename = "de")]
+    German,
+    #[serde(rename = "it")]
+    Italian,
+    #[serde(rename = "pt")]
+    Portuguese,
+    #[serde(rename = "nl")]
+    Dutch,
+}
+
+/// The names of the months and of the days of the week, starting on Monday
+struct Names {
+    months: [&'static str; 12],
+    short_months: [&'static str; 12],
+    weekdays: [&'static str; 7],
+    short_weekdays: [&'static str; 7],
+}
+
+const ENGLISH: Names = Names {
+    months: [
+        "January",
+        "February",
+        "March",
+        "April",
+        "May",
+        "June",
+        "July",
+        "August",
+        "September",
+        "October",
+        "November",
+        "December",
+    ],
+    short_months: [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ],
+    weekdays: [
+        "Monday",
+        "Tuesday",
+        "Wednesday",
+        "Thursday",
+        "Friday",
+        "Saturday",
+        "Sunday",
+    ],
+    short_weekdays: ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"],
+};
+
+const SPANISH: Names = Names {
+    months: [
+        "enero",
+        "febrero",
+        "marzo",
+        "abril",
+        "mayo",
+        "junio",
+        "julio",
+        "agosto",
+        "septiembre",
+        "octubre",
+        "noviembre",
+        "diciembre",
+    ],
+    short_months: [
+        "ene", "feb", "mar", "abr", "may", "jun", "jul", "ago", "sept", "oct", "nov", "dic",
+    ],
+    weekdays: [
+        "lunes",
+        "martes",
+        "miércoles",
+        "jueves",
+        "viernes",
+        "sábado",
+        "domingo",
+    ],
+    short_weekdays: ["lun", "mar", "mié", "jue", "vie", "sáb", "dom"],
+};
+
+const FRENCH: Names = Names {
+    months: [
+        "janvier",
+        "février",
+        "mars",
+        "avril",
+        "mai",
+        "juin",
+        "juillet",
+        "août",
+        "septembre",
+        "octobre",
+        "novembre",
+        "décembre",
+    ],
+    short_months: [
+        "janv.", "févr.", "mars", "avr.", "mai", "juin", "juil.", "août", "sept.", "oct.", "nov.",
+        "déc.",
+    ],
+    weekdays: [
+        "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche",
+    ],
+    short_weekdays: ["lun.", "mar.", "mer.", "jeu.", "ven.", "sam.", "dim."],
+};
+
+const GERMAN: Names = Names {
+    months: [
+        "Januar",
+        "Februar",
+        "März",
+        "April",
+        "Mai",
+        "Juni",
+        "Juli",
+        "August",
+        "September",
+        "Oktober",
+        "November",
+        "Dezember",
+    ],
+    short_months: [
+        "Jan.", "Feb.", "März", "Apr.", "Mai", "Juni", "Juli", "Aug.", "Sept.", "Okt.", "Nov.",
+        "Dez.",
+    ],
+    weekdays: [
+        "Montag",
+        "Dienstag",
+        "Mittwoch",
+        "Donnerstag",
+        "Freitag",
+        "Samstag",
+        "Sonntag",
+    ],
+    short_weekdays: ["Mo.", "Di.", "Mi.", "Do.", "Fr.", "Sa.", "So."],
+};
+
+const ITALIAN: Names = Names {
+    months: [
+        "gennaio",
+        "febbraio",
+        "marzo",
+        "aprile",
+        "maggio",
+        "giugno",
+        "luglio",
+        "agosto",
+        "settembre",
+        "ottobre",
+        "novembre",
+        "dicembre",
+    ],
+    short_months: [
+        "gen", "feb", "mar", "apr", "mag", "giu", "lug", "ago", "set", "ott", "nov", "dic",
+    ],
+    weekdays: [
+        "lunedì",
+        "martedì",
+        "mercoledì",
+        "giovedì",
+        "venerdì",
+        "sabato",
+        "domenica",
+    ],
+    short_weekdays: ["lun", "mar", "mer", "gio", "ven", "sab", "dom"],
+};
+
+const PORTUGUESE: Names = Names {
+    months: [
+        "janeiro",
+        "fevereiro",
+        "março",
+        "abril",
+        "maio",
+        "junho",
+        "julho",
+        "agosto",
+        "setembro",
+        "outubro",
+        "novembro",
+        "dezembro",
+    ],
+    short_months: [
+        "jan.", "fev.", "mar.", "abr.", "mai.", "jun.", "jul.", "ago.", "set.", "out.", "nov.",
+        "dez.",
+    ],
+    weekdays: [
+        "segunda-feira",
+        "terça-feira",
+        "quarta-feira",
+        "quinta-feira",
+        "sexta-feira",
+        "sábado",
+        "domingo",
+    ],
+    short_weekdays: ["seg.", "ter.", "qua.", "qui.", "sex.", "sáb.", "dom."],
+};
+
+const DUTCH: Names = Names {
+    months: [
+        "januari",
+        "februari",
+        "maart",
+        "april",
+        "mei",
+        "juni",
+        "juli",
+        "augustus",
+        "september",
+        "oktober",
+        "november",
+        "december",
+    ],
+    short_months: [
+        "jan", "feb", "mrt", "apr", "mei", "jun", "jul", "aug", "sep", "okt", "nov", "dec",
+    ],
+    weekdays: [
+        "maandag",
+        "dinsdag",
+        "woensdag",
+        "donderdag",
+        "vrijdag",
+        "zaterdag",
+        "zondag",
+    ],
+    short_weekdays: ["ma", "di", "wo", "do", "vr", "za", "zo"],
+};
+
+impl Locale {
+    fn names(&self) -> &'static Names {
+        match self {
+            Locale::English | Locale::AmericanEnglish => &ENGLISH,
+            Locale::Spanish => &SPANISH,
+            Locale::French => &FRENCH,
+            Locale::German => &GERMAN,
+            Locale::Italian => &ITALIAN,
+            Locale::Portuguese => &PORTUGUESE,
+            Locale::Dutch => &DUTCH,
+        }
+    }
+
+    /// The day weeks start on, unless the settings say otherwise
+    pub fn week_start(&self) -> DayOfWeek {
+        match self {
+            Locale::AmericanEnglish => DayOfWeek::Sunday,
+            _ => DayOfWeek::Monday,
+        }
+    }
+
+    /// Formats `date` like `DateTime::format`, with `%a`, `%A`, `%b`, `%B` and `%h` in
+    /// this locale
+    pub fn format<Tz: TimeZone>(&self, date: &DateTime<Tz>, format: &str) -> String
+    where
+        Tz::Offset: Display,
+    {
+        let names = self.names();
+        let weekday = date.weekday().num_days_from_monday() as usize;
+        let month = date.month0() as usize;
+
+        let mut localized = String::with_capacity(format.len());
+        let mut chars = format.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                localized.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('a') => localized.push_str(names.short_weekdays[weekday]),
+                Some('A') => localized.push_str(names.weekdays[weekday]),
+                Some('b' | 'h') => localized.push_str(names.short_months[month]),
+                Some('B') => localized.push_str(names.months[month]),
+                // Everything else, including `%%`, is left to chrono
+                Some(other) => {
+                    localized.push('%');
+                    localized.push(other);
+                }
+                None => localized.push('%'),
+            }
+        }
+        date.format(&localized).to_string()
+    }
+
+    /// The day of the week named `s`, in full or abbreviated, in this locale or in
+    /// English. Case and accents don't matter.
+    pub fn parse_weekday(&self, s: &str) -> Option<DayOfWeek> {
+        if let Ok(day) = s.parse() {
+            return Some(day);
+        }
+        let names = self.names();
+        let position = find_name(s, &names.weekdays, &names.short_weekdays)?;
+        Some(DayOfWeek::from_int(position as u32 + 1))
+    }
+
+    /// The month (1 to 12) named `s`, in full or abbreviated, in this locale or in
+    /// English. Case and accents don't matter.
+    pub fn parse_month(&self, s: &str) -> Option<u32> {
+        [self.names(), &ENGLISH]
+            .iter()
+            .find_map(|names| find_name(s, &names.months, &names.short_months))
+            .map(|position| position as u32 + 1)
+    }
+}
+
+/// The position of `s` in `names`, as a whole name, an abbreviation or the first three
+/// letters or more of a name
+fn find_name(s: &str, names: &[&str], short_names: &[&str]) -> Option<usize> {
+    let s = fold(s.trim_end_matches('.'));
+    if s.is_empty() {
+        return None;
+    }
+    let exact = |names: &[&str]| {
+        names
+            .iter()
+            .position(|name| fold(name.trim_end_matches('.')) == s)
+    };
+    exact(names).or_else(|| exact(short_names)).or_else(|| {
+        names
+            .iter()
+            .position(|name| s.chars().count() >= 3 && fold(name).starts_with(&s))
+    })
+}
+
+/// Lowercases `s` and strips the accents of the letters used in the supported locales
+fn fold(s: &str) -> String {
+    s.to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'á' | 'à' | 'â' | 'ä' | 'ã' => 'a',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'í' | 'ì' | 'î' | 'ï' => 'i',
+            'ó' | 'ò' | 'ô' | 'ö' | 'õ' => 'o',
+            'ú' | 'ù' | 'û' | 'ü' => 'u',
+            'ç' => 'c',
+            'ñ' => 'n',
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{configuration::Settings, utils};
+    use chrono::{NaiveDate, Utc};
+
+    const LOCALES: [Locale; 8] = [
+        Locale::English,
+        Locale::AmericanEnglish,
+        Locale::Spanish,
+        Locale::French,
+        Locale::German,
+        Locale::Italian,
+        Locale::Portuguese,
+        Locale::Dutch,
+    ];
+
+    #[test]
+    fn every_name_reads_back() {
+        for locale in LOCALES {
+            let names = locale.names();
+            for (i, (name, short)) in names.weekdays.iter().zip(names.short_weekdays).enumerate() {
+                let day = DayOfWeek::from_int(i as u32 + 1);
+                assert_eq!(locale.parse_weekday(name), Some(day.clone()), "{}", name);
+                assert_eq!(locale.parse_weekday(short), Some(day), "{}", short);
+            }
+            for (i, (name, short)) in names.months.iter().zip(names.short_months).enumerate() {
+                let month = i as u32 + 1;
+                assert_eq!(locale.parse_month(name), Some(month), "{}", name);
+                assert_eq!(locale.parse_month(short), Some(month), "{}", short);
+            }
+        }
+    }
+
+    #[test]
+    fn names_are_matched_loosely() {
+        let cases = [
+            ("miércoles", Some(3)),
+            ("MIERCOLES", Some(3)),
+            ("mié", Some(3)),
+            ("mierc", Some(3)),
+            ("sab", Some(6)),
+            // English names work in every locale
+            ("wed", Some(3)),
+            ("Sunday", Some(7)),
+            ("mi", None),
+            ("miercolesx", None),
+            ("", None),
+        ];
+        for (s, expected) in cases {
+            let day = Locale::Spanish.parse_weekday(s);
+            assert_eq!(day, expected.map(DayOfWeek::from_int), "'{}'", s);
+        }
+
+        let cases = [
+            ("März", Some(3)),
+            ("marz", Some(3)),
+            ("Mär", Some(3)),
+            ("okt.", Some(10)),
+            ("Dezember", Some(12)),
+            ("october", Some(10)),
+            ("ma", None),
+        ];
+        for (s, expected) in cases {
+            assert_eq!(Locale::German.parse_month(s), expected, "'{}'", s);
+        }
+    }
+
+    #[test]
+    fn formats_names_in_the_locale() {
+        // A Wednesday
+        let date = Utc.with_ymd_and_hms(2026, 3, 4, 17, 30, 0).unwrap();
+        let cases = [
+            (Locale::English, "%a %d %b", "Wed 04 Mar"),
+            (
+                Locale::Spanish,
+                "%A %-d de %B, %H:%M",
+                "miércoles 4 de marzo, 17:30",
+            ),
+            (Locale::Spanish, "%a %h", "mié mar"),
+            (Locale::French, "%a %-d %b %Y", "mer. 4 mars 2026"),
+            (Locale::German, "%A, %-d. %B", "Mittwoch, 4. März"),
+            (Locale::Dutch, "%a %-d %b", "wo 4 mrt"),
+            // Escaped percent signs stay escaped
+            (Locale::German, "100%% %a", "100% Mi."),
+            (Locale::German, "%%a", "%a"),
+        ];
+        for (locale, format, expected) in cases {
+            assert_eq!(
+                locale.format(&date, format),
+                expected,
+                "{:?} {}",
+                locale,
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn weeks_start_on_sunday_in_the_us() {
+        for locale in LOCALES {
+            let expected = match locale {
+                Locale::AmericanEnglish => DayOfWeek::Sunday,
+                _ => DayOfWeek::Monday,
+            };
+            assert_eq!(locale.week_start(), expected, "{:?}", locale);
+        }
+    }
+
+    #[test]
+    fn weeks_can_start_on_sunday() {
+        let mut settings = Settings::default();
+        settings.locale = Locale::AmericanEnglish;
+        assert_eq!(settings.week_start(), DayOfWeek::Sunday);
+        settings.first_day_of_week = Some(DayOfWeek::Monday);
+        assert_eq!(settings.week_start(), DayOfWeek::Monday);
+        settings.locale = Locale::German;
+        settings.first_day_of_week = Some(DayOfWeek::Sunday);
+        assert_eq!(settings.week_start(), DayOfWeek::Sunday);
+
+        let date = |s: &str| s.parse::<NaiveDate>().unwrap();
+        let cases = [
+            ("2026-03-04", DayOfWeek::Sunday, "2026-03-01"),
+            ("2026-03-04", DayOfWeek::Monday, "2026-03-02"),
+            ("2026-03-01", DayOfWeek::Sunday, "2026-03-01"),
+            ("2026-03-01", DayOfWeek::Monday, "2026-02-23"),
+            ("2026-02-28", DayOfWeek::Sunday, "2026-02-22"),
+        ];
+        for (day, week_start, expected) in cases {
+            let start = utils::start_of_week(date(day), &week_start);
+            assert_eq!(start, date(expected), "{} with {:?}", day, week_start);
+        }
+    }
+}
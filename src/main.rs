@@ -1,18 +1,32 @@
-use todui::configuration::get_configuration;
+use anyhow::Result;
+use clap::Parser;
+use todui::configuration::{get_configuration, Paths};
+use todui::error::StorageError;
 use todui::{app::App, cli, ui};
 
 fn main() {
-    // Check args, if none, run ui, else run cli
-    let settings = get_configuration();
-    let app = App::new(settings);
+    if let Err(e) = run() {
+        report_error(&e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<()> {
+    let args = cli::Args::parse();
+    let paths = Paths::resolve(args.config, args.db)?;
+    let settings = get_configuration(&paths)?;
+    let app = App::new(settings, &paths)?;
 
-    let res = if std::env::args().len() > 1 {
-        cli::start_cli(app)
-    } else {
-        ui::start_ui(app)
-    };
+    // If there is no command, run ui, else run cli
+    match args.command {
+        Some(command) => cli::start_cli(app, command),
+        None => ui::start_ui(app),
+    }
+}
 
-    if let Err(e) = res {
-        eprintln!("{}", e);
+fn report_error(e: &anyhow::Error) {
+    eprintln!("error: {}", e);
+    if let Some(storage_error) = e.downcast_ref::<StorageError>() {
+        eprintln!("hint: {}", storage_error.hint());
     }
 }
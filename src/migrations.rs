@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use chrono::{DateTime, NaiveTime};
+use serde::Serialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::{app::Id, configuration::Settings, error::StorageError, task::Task};
+
+/// Upgrades a file from one schema version to the next
+type Migration = fn(Value) -> Value;
+
+pub const TASKS_SCHEMA_VERSION: u32 = 5;
+pub const SETTINGS_SCHEMA_VERSION: u32 = 2;
+
+// `TASKS_MIGRATIONS[i]` upgrades a tasks file from version `i + 1` to `i + 2`
+const TASKS_MIGRATIONS: [Migration; TASKS_SCHEMA_VERSION as usize - 1] = [
+    tasks_v1_to_v2,
+    tasks_v2_to_v3,
+    tasks_v3_to_v4,
+    tasks_v4_to_v5,
+];
+const SETTINGS_MIGRATIONS: [Migration; SETTINGS_SCHEMA_VERSION as usize - 1] = [settings_v1_to_v2];
+
+/// The layout of `tasks.json`
+#[derive(Serialize)]
+pub struct TasksFile<'a> {
+    pub schema_version: u32,
+    pub tasks: &'a HashMap<Id, Task>,
+}
+
+impl<'a> TasksFile<'a> {
+    pub fn new(tasks: &'a HashMap<Id, Task>) -> Self {
+        Self {
+            schema_version: TASKS_SCHEMA_VERSION,
+            tasks,
+        }
+    }
+}
+
+/// The layout of `settings.json`: the settings themselves plus a version field
+#[derive(Serialize)]
+pub struct SettingsFile<'a> {
+    pub schema_version: u32,
+    #[serde(flatten)]
+    pub settings: &'a Settings,
+}
+
+impl<'a> SettingsFile<'a> {
+    pub fn new(settings: &'a Settings) -> Self {
+        Self {
+            schema_version: SETTINGS_SCHEMA_VERSION,
+            settings,
+        }
+    }
+}
+
+/// Returns the tasks file upgraded to the current version, or `None` if it is up to date
+pub fn upgrade_tasks(path: &Path, contents: &str) -> Result<Option<Value>, StorageError> {
+    upgrade(path, contents, TASKS_SCHEMA_VERSION, &TASKS_MIGRATIONS)
+}
+
+/// Returns the settings file upgraded to the current version, or `None` if it is up to date
+pub fn upgrade_settings(path: &Path, contents: &str) -> Result<Option<Value>, StorageError> {
+    upgrade(
+        path,
+        contents,
+        SETTINGS_SCHEMA_VERSION,
+        &SETTINGS_MIGRATIONS,
+    )
+}
+
+fn upgrade(
+    path: &Path,
+    contents: &str,
+    supported: u32,
+    migrations: &[Migration],
+) -> Result<Option<Value>, StorageError> {
+    let mut value: Value =
+        serde_json::from_str(contents).map_err(|e| StorageError::parse(path, &e, None))?;
+
+    let found = schema_version(&value);
+    if found > supported {
+        return Err(StorageError::SchemaVersion {
+            path: path.to_path_buf(),
+            found,
+            supported,
+        });
+    }
+    if found == supported {
+        return Ok(None);
+    }
+
+    for (from, migration) in (found..).zip(&migrations[found as usize - 1..]) {
+        value = migration(value);
+        value["schema_version"] = json!(from + 1);
+    }
+    Ok(Some(value))
+}
+
+/// Files written before versioning was introduced have no version field
+fn schema_version(value: &Value) -> u32 {
+    value
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .map(|v| v.max(1) as u32)
+        .unwrap_or(1)
+}
+
+/// Moves the bare `{id: task}` map into the `tasks` field of the envelope
+fn tasks_v1_to_v2(value: Value) -> Value {
+    json!({ "tasks": value })
+}
+
+/// Gives every task a series id
+fn tasks_v2_to_v3(mut value: Value) -> Value {
+    if let Some(tasks) = value["tasks"].as_object_mut() {
+        tasks.values_mut().for_each(add_series_id);
+    }
+    value
+}
+
+/// Gives a task that predates series ids a new one. Also used to upgrade SQLite rows
+pub fn add_series_id(task: &mut Value) {
+    if let Some(task) = task.as_object_mut() {
+        task.entry("series_id")
+            .or_insert_with(|| json!(Uuid::new_v4()));
+    }
+}
+
+/// Tells the tasks due on a day from the ones due at a time of day
+fn tasks_v3_to_v4(mut value: Value) -> Value {
+    if let Some(tasks) = value["tasks"].as_object_mut() {
+        tasks.values_mut().for_each(add_has_time);
+    }
+    value
+}
+
+/// Tasks due on a day used to be due at 23:59:59 that day, since every task had a time.
+/// Also used to upgrade SQLite rows
+pub fn add_has_time(task: &mut Value) {
+    let Some(task) = task.as_object_mut() else {
+        return;
+    };
+    let date_only = task
+        .get("date")
+        .and_then(Value::as_str)
+        .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+        .is_some_and(|date| date.time() == NaiveTime::from_hms_opt(23, 59, 59).unwrap());
+    task.entry("has_time").or_insert(json!(!date_only));
+}
+
+/// Keeps the tasks due on a day on that day, whatever the local time zone
+fn tasks_v4_to_v5(mut value: Value) -> Value {
+    if let Some(tasks) = value["tasks"].as_object_mut() {
+        tasks.values_mut().for_each(float_dates);
+    }
+    value
+}
+
+/// Tasks due on a day used to be stored at the end of that day in the local time zone
+/// at the time. Now they're stored as the day alone. Also used to upgrade SQLite rows
+pub fn float_dates(task: &mut Value) {
+    let Some(task) = task.as_object_mut() else {
+        return;
+    };
+    if task.get("has_time").and_then(Value::as_bool) != Some(false) {
+        return;
+    }
+    let day = task
+        .get("date")
+        .and_then(Value::as_str)
+        .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+        .map(|date| date.format("%Y-%m-%d").to_string());
+    if let Some(day) = day {
+        task.insert("date".to_string(), json!(day));
+    }
+}
+
+/// Adds the version field, and fills in the settings added since then, like
+/// `keybindings.next_group`, with their defaults
+fn settings_v1_to_v2(value: Value) -> Value {
+    let mut settings =
+        serde_json::to_value(Settings::default()).expect("Unable to serialize settings");
+    merge(&mut settings, value);
+    settings
+}
+
+/// Overwrites `defaults` with `value`, key by key in nested objects
+fn merge(defaults: &mut Value, value: Value) {
+    match (defaults, value) {
+        (Value::Object(defaults), Value::Object(value)) => {
+            for (key, value) in value {
+                match defaults.get_mut(&key) {
+                    Some(default) => merge(default, value),
+                    None => {
+                        defaults.insert(key, value);
+                    }
+                }
+            }
+        }
+        (defaults, value) => *defaults = value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils;
+    use chrono::NaiveDate;
+    use crossterm::event::KeyCode;
+    use std::fs;
+
+    // A settings file from before versioning, with no `next_group` or `prev_group`
+    const SETTINGS_V1: &str = r#"{
+        "date_formats": {
+            "display_date_format": "%a %b %-d",
+            "display_datetime_format": "%a %b %-d at %-H:%M",
+            "input_date_format": "%d-%m-%Y",
+            "input_datetime_format": "%d-%m-%Y %H:%M"
+        },
+        "show_complete": false,
+        "current_group": "Work",
+        "keybindings": {
+            "quit": "x",
+            "down": "j",
+            "up": "k",
+            "complete_task": "Space"
+        }
+    }"#;
+
+    #[test]
+    fn tasks_due_at_the_end_of_the_day_lose_their_time() {
+        let cases = [
+            // Stored by older versions for tasks due on a day
+            (json!({"date": "2026-03-10T23:59:59-08:00"}), false),
+            (json!({"date": "2026-03-10T23:59:59+13:00"}), false),
+            // Entered by the user
+            (json!({"date": "2026-03-10T23:59:00-08:00"}), true),
+            (json!({"date": "2026-03-10T09:30:00+01:00"}), true),
+            (json!({"date": null}), true),
+            (json!({}), true),
+            // Already upgraded
+            (
+                json!({"date": "2026-03-10T23:59:59-08:00", "has_time": true}),
+                true,
+            ),
+        ];
+        for (mut task, has_time) in cases {
+            let before = task.clone();
+            add_has_time(&mut task);
+            assert_eq!(task["has_time"], json!(has_time), "{before}");
+        }
+    }
+
+    #[test]
+    fn tasks_due_on_a_day_keep_their_day() {
+        // Written at the end of the 10th in two zones 21 hours apart, which is the 10th in
+        // one and the 11th in the other in UTC
+        let v4 = json!({"schema_version": 4, "tasks": {
+            "1": {"id": 1, "name": "west", "date": "2026-03-10T23:59:59-08:00",
+                "has_time": false, "repeats": "Never", "group": null, "description": null,
+                "url": null, "complete": false},
+            "2": {"id": 2, "name": "east", "date": "2026-03-10T23:59:59+13:00",
+                "has_time": false, "repeats": "Never", "group": null, "description": null,
+                "url": null, "complete": false},
+            "3": {"id": 3, "name": "timed", "date": "2026-03-10T23:59:59+13:00",
+                "has_time": true, "repeats": "Never", "group": null, "description": null,
+                "url": null, "complete": false},
+        }});
+        let upgraded = upgrade_tasks(Path::new("tasks.json"), &v4.to_string())
+            .unwrap()
+            .unwrap();
+        let tasks = &upgraded["tasks"];
+        assert_eq!(tasks["1"]["date"], json!("2026-03-10"));
+        assert_eq!(tasks["2"]["date"], json!("2026-03-10"));
+        assert_eq!(tasks["3"]["date"], json!("2026-03-10T23:59:59+13:00"));
+
+        // Read back in the local zone, whichever it is, they're due on the 10th
+        let day = NaiveDate::from_ymd_opt(2026, 3, 10).unwrap();
+        for id in ["1", "2"] {
+            let task: Task = serde_json::from_value(tasks[id].clone()).unwrap();
+            assert_eq!(task.date.unwrap().date_naive(), day);
+            assert_eq!(
+                serde_json::to_value(&task).unwrap()["date"],
+                json!("2026-03-10")
+            );
+        }
+    }
+
+    #[test]
+    fn old_settings_are_filled_in_with_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("settings.json");
+        fs::write(&file, SETTINGS_V1).unwrap();
+
+        let settings = utils::load_settings(&file).unwrap();
+        assert!(!settings.show_complete);
+        assert_eq!(settings.current_group.as_deref(), Some("Work"));
+        assert_eq!(settings.keybindings.quit, KeyCode::Char('x'));
+        assert_eq!(settings.keybindings.down, KeyCode::Char('j'));
+        assert_eq!(settings.keybindings.next_group, KeyCode::Right);
+        assert_eq!(settings.keybindings.prev_group, KeyCode::Left);
+        assert_eq!(settings.history_size, Settings::default().history_size);
+
+        let contents = fs::read_to_string(&file).unwrap();
+        let value: Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(value["schema_version"], json!(SETTINGS_SCHEMA_VERSION));
+        assert_eq!(value["keybindings"]["quit"], json!("x"));
+        assert_eq!(value["keybindings"]["next_group"], json!("Right"));
+        assert_eq!(
+            value["date_formats"]["input_date_format"],
+            json!("%d-%m-%Y")
+        );
+        assert!(upgrade_settings(&file, &contents).unwrap().is_none());
+    }
+}
@@ -0,0 +1,217 @@
+use crate::{day_of_week::DayOfWeek, locale::Locale, utils};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, Days, Duration, Local, Months, NaiveDate, NaiveTime, TimeZone};
+
+/// Parses dates the way people write them, like "tomorrow", "next fri 5pm", "in 3 days",
+/// "eod", "mon" or "dec 24", relative to `now`. Day and month names can be in `locale`.
+/// Also returns whether a time of day was given, like `utils::parse_date`.
+pub fn parse(s: &str, now: DateTime<Local>, locale: Locale) -> Result<(DateTime<Local>, bool)> {
+    let lower = s.trim().to_lowercase();
+    let mut words: Vec<&str> = lower
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|w| !w.is_empty() && *w != "at" && *w != "on")
+        .collect();
+
+    if let ["in", n, unit] = words.as_slice() {
+        if let Some(date) = in_hours(n, unit, now) {
+            return Ok((date, true));
+        }
+    }
+
+    let mut time = None;
+    let mut i = 0;
+    while i < words.len() {
+        // "5 pm" is the same as "5pm"
+        let (word, len) = match words.get(i + 1) {
+            Some(&suffix) if suffix == "am" || suffix == "pm" => {
+                (format!("{}{}", words[i], suffix), 2)
+            }
+            _ => (words[i].to_string(), 1),
+        };
+        match parse_time(&word) {
+            Some(t) if time.is_none() => {
+                time = Some(t);
+                words.drain(i..i + len);
+            }
+            _ => i += 1,
+        }
+    }
+
+    let day = parse_day(&words, now.date_naive(), locale)
+        .ok_or_else(|| anyhow!("Unable to parse date '{}'", s.trim()))?;
+    let Some(time) = time else {
+        return Ok((utils::end_of_day(day), false));
+    };
+    let naive = day.and_time(time);
+    let date = Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .ok_or_else(|| anyhow!("{} doesn't exist in the local time zone", naive))?;
+    Ok((date, true))
+}
+
+/// "in 4 hours" or "in 30 minutes"
+fn in_hours(n: &str, unit: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let n: i64 = n.parse().ok()?;
+    match unit.strip_suffix('s').unwrap_or(unit) {
+        "minute" | "min" => Some(now + Duration::minutes(n)),
+        "hour" | "hr" => Some(now + Duration::hours(n)),
+        _ => None,
+    }
+}
+
+/// "in 3 days", "in 2 weeks", "in 1 month" or "in 1 year"
+fn in_days(n: &str, unit: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let n: u32 = n.parse().ok()?;
+    match unit.strip_suffix('s').unwrap_or(unit) {
+        "day" => today.checked_add_days(Days::new(n.into())),
+        "week" => today.checked_add_days(Days::new(7 * u64::from(n))),
+        "month" => today.checked_add_months(Months::new(n)),
+        "year" => today.checked_add_months(Months::new(12 * n)),
+        _ => None,
+    }
+}
+
+/// "5pm", "5:30pm", "17:00", "noon" or "midnight"
+fn parse_time(word: &str) -> Option<NaiveTime> {
+    match word {
+        "noon" => return NaiveTime::from_hms_opt(12, 0, 0),
+        "midnight" => return NaiveTime::from_hms_opt(0, 0, 0),
+        _ => {}
+    }
+
+    let (clock, offset) = if let Some(clock) = word.strip_suffix("am") {
+        (clock, Some(0))
+    } else if let Some(clock) = word.strip_suffix("pm") {
+        (clock, Some(12))
+    } else {
+        (word, None)
+    };
+    let (hour, minute) = match clock.split_once(':') {
+        Some((hour, minute)) if minute.len() == 2 => (hour.parse().ok()?, minute.parse().ok()?),
+        // A bare number is a day of the month unless it has am or pm
+        None if offset.is_some() => (clock.parse().ok()?, 0),
+        _ => return None,
+    };
+    let hour = match offset {
+        Some(offset) if (1..=12).contains(&hour) => hour % 12 + offset,
+        Some(_) => return None,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+fn parse_day(words: &[&str], today: NaiveDate, locale: Locale) -> Option<NaiveDate> {
+    match words {
+        [] | ["today" | "tod" | "eod" | "tonight"] => Some(today),
+        ["tomorrow" | "tmr" | "tmrw"] => today.succ_opt(),
+        ["yesterday"] => today.pred_opt(),
+        ["next", "week"] => today.checked_add_days(Days::new(7)),
+        ["next", "month"] => today.checked_add_months(Months::new(1)),
+        ["next", "year"] => today.checked_add_months(Months::new(12)),
+        ["in", n, unit] => in_days(n, unit, today),
+        ["next", day] | [day] if locale.parse_weekday(day).is_some() => {
+            next_weekday(today, &locale.parse_weekday(day)?)
+        }
+        [a, b] => month_and_day(a, b, today, locale).or_else(|| month_and_day(b, a, today, locale)),
+        [a, b, year] => {
+            let year = year.parse().ok()?;
+            let day = month_and_day(a, b, today, locale)
+                .or_else(|| month_and_day(b, a, today, locale))?;
+            day.with_year(year)
+        }
+        _ => None,
+    }
+}
+
+/// The first `day` after `today`, so "mon" on a Monday is a week later
+fn next_weekday(today: NaiveDate, day: &DayOfWeek) -> Option<NaiveDate> {
+    let current = today.weekday().number_from_monday();
+    let days = (day.to_int() + 7 - current - 1) % 7 + 1;
+    today.checked_add_days(Days::new(days.into()))
+}
+
+/// The next `month` `day` that isn't in the past
+fn month_and_day(month: &str, day: &str, today: NaiveDate, locale: Locale) -> Option<NaiveDate> {
+    let month = locale.parse_month(month)?;
+    let day = day
+        .trim_end_matches(|c: char| c.is_ascii_alphabetic())
+        .parse()
+        .ok()?;
+    let this_year = NaiveDate::from_ymd_opt(today.year(), month, day);
+    match this_year {
+        Some(date) if date >= today => Some(date),
+        // Feb 29 only exists in leap years
+        _ => (1..=8).find_map(|n| NaiveDate::from_ymd_opt(today.year() + n, month, day)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(s: &str) -> DateTime<Local> {
+        let naive = chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap();
+        Local.from_local_datetime(&naive).earliest().unwrap()
+    }
+
+    #[test]
+    fn parses_relative_to_now() {
+        // A Wednesday
+        let now = local("2026-06-10 10:00:00");
+        let cases = [
+            ("tomorrow", "2026-06-11 23:59:59", false),
+            ("eod", "2026-06-10 23:59:59", false),
+            ("in 3 days", "2026-06-13 23:59:59", false),
+            ("in 2 weeks", "2026-06-24 23:59:59", false),
+            ("fri", "2026-06-12 23:59:59", false),
+            ("wed", "2026-06-17 23:59:59", false),
+            ("next fri 5pm", "2026-06-12 17:00:00", true),
+            ("next fri at 5 pm", "2026-06-12 17:00:00", true),
+            ("in 2 hours", "2026-06-10 12:00:00", true),
+            ("in 30 mins", "2026-06-10 10:30:00", true),
+            ("noon", "2026-06-10 12:00:00", true),
+            ("tomorrow midnight", "2026-06-11 00:00:00", true),
+            ("17:00", "2026-06-10 17:00:00", true),
+            ("9:30am", "2026-06-10 09:30:00", true),
+            ("12am", "2026-06-10 00:00:00", true),
+            ("dec 24", "2026-12-24 23:59:59", false),
+            ("24 dec", "2026-12-24 23:59:59", false),
+            ("December 24th", "2026-12-24 23:59:59", false),
+            ("dec 24 2027", "2027-12-24 23:59:59", false),
+            ("dec 24, 2027 9am", "2027-12-24 09:00:00", true),
+            // A date that has passed this year is next year's
+            ("jan 5", "2027-01-05 23:59:59", false),
+            ("feb 29", "2028-02-29 23:59:59", false),
+        ];
+        for (input, expected, has_time) in cases {
+            let parsed = parse(input, now, Locale::English).unwrap();
+            assert_eq!(parsed, (local(expected), has_time), "parsing '{}'", input);
+        }
+    }
+
+    #[test]
+    fn invalid_dates_are_rejected() {
+        let now = local("2026-06-10 10:00:00");
+        let cases = [
+            "someday",
+            "next",
+            "in 3 fortnights",
+            "in a week",
+            "13pm",
+            "25:00",
+            "9:5am",
+            "feb 30",
+            "feb 30 2028",
+            "mon tue",
+            "dec 24 next year",
+        ];
+        for input in cases {
+            assert!(
+                parse(input, now, Locale::English).is_err(),
+                "'{}' should not parse",
+                input
+            );
+        }
+    }
+}
@@ -1,10 +1,11 @@
-use crate::day_of_week::DayOfWeek;
-use anyhow::Result;
+use crate::{day_of_week::DayOfWeek, locale::Locale, rrule::RRule};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, Days, Local, Months, NaiveDate, NaiveTime, Offset, TimeZone};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use std::{fmt::Display, str::FromStr};
 
-#[derive(Serialize, Deserialize, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
 pub enum Repeat {
     Never,
     Daily,
@@ -12,35 +13,345 @@ pub enum Repeat {
     Monthly,
     Yearly,
     DaysOfWeek(Vec<DayOfWeek>),
+    /// Every `n` days
+    EveryDays(u32),
+    /// Every `interval` weeks on the given days, or on the weekday of the due date if
+    /// there are none. Weeks start on Monday, whatever `first_day_of_week` is set to.
+    EveryWeeks {
+        interval: u32,
+        days: Vec<DayOfWeek>,
+    },
+    /// The `nth` `day` of every month. Negative values count from the end of the month,
+    /// so -1 is the last one. Months without an `nth` `day` are skipped.
+    NthWeekdayOfMonth {
+        nth: i8,
+        day: DayOfWeek,
+    },
+    /// The same day of every month, or the last day of the months that are too short
+    DayOfMonth(u32),
+    /// The first Monday to Friday of January, April, July and October
+    FirstWeekdayOfQuarter,
+    /// An iCalendar recurrence rule, for tasks shared with calendar tools
+    RRule(RRule),
+}
+
+/// What the next occurrence of a repeating task is counted from
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum RepeatFrom {
+    /// The next occurrence follows the current one, however late it was completed
+    #[default]
+    DueDate,
+    /// The next occurrence is counted from the day the current one was completed
+    CompletionDate,
+}
+
+impl FromStr for RepeatFrom {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<RepeatFrom> {
+        match s.trim().to_lowercase().as_str() {
+            "" | "due" | "due date" => Ok(RepeatFrom::DueDate),
+            "completion" | "completion date" => Ok(RepeatFrom::CompletionDate),
+            _ => Err(anyhow!("Expected 'due date' or 'completion date'")),
+        }
+    }
+}
+
+impl Display for RepeatFrom {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RepeatFrom::DueDate => write!(f, "Due date"),
+            RepeatFrom::CompletionDate => write!(f, "Completion date"),
+        }
+    }
+}
+
+pub fn serialize_time<S>(time: &NaiveTime, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.serialize_str(&time.format("%H:%M:%S").to_string())
+}
+
+pub fn deserialize_time<'de, D>(deserializer: D) -> Result<NaiveTime, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    NaiveTime::parse_from_str(&s, "%H:%M:%S")
+        .map_err(|e| serde::de::Error::custom(format!("invalid time '{}': {}", s, e)))
+}
+
+/// Where the occurrences of a repeating task are meant to fall, which the due date can
+/// only approximate when a month is too short or a DST change skips the time of day
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Anchor {
+    /// The day of the month of monthly and yearly repeats
+    pub day: u32,
+    #[serde(
+        serialize_with = "serialize_time",
+        deserialize_with = "deserialize_time"
+    )]
+    pub time: NaiveTime,
+}
+
+impl Anchor {
+    pub fn of<Tz: TimeZone>(date: &DateTime<Tz>) -> Anchor {
+        Anchor {
+            day: date.day(),
+            time: date.time(),
+        }
+    }
 }
 
 impl Repeat {
-    pub fn parse_from_str(s: &str) -> Result<Repeat> {
-        match s.to_lowercase().as_str() {
-            "never" | "" => Ok(Repeat::Never),
-            "daily" => Ok(Repeat::Daily),
-            "weekly" => Ok(Repeat::Weekly),
-            "monthly" => Ok(Repeat::Monthly),
-            "yearly" => Ok(Repeat::Yearly),
-            _ => {
-                let days: Vec<Result<DayOfWeek>> = s
-                    .split(',')
-                    .map(|s| s.trim())
-                    .map(DayOfWeek::from_str)
-                    .collect();
-                if days.iter().any(|d| d.is_err()) {
-                    return Err(anyhow::anyhow!("Invalid day of the week"));
-                }
+    /// Parses a rule like "daily" or "every 2 weeks on tue,thu". Day names can be in
+    /// `locale`.
+    pub fn parse_from_str(s: &str, locale: Locale) -> Result<Repeat> {
+        if s.contains('=') {
+            return Ok(Repeat::RRule(s.parse()?));
+        }
 
-                let days: Vec<DayOfWeek> =
-                    days.iter().map(|d| d.as_ref().unwrap()).cloned().collect();
+        let lower = s.trim().to_lowercase();
+        let words: Vec<&str> = lower.split_whitespace().collect();
+        match words.as_slice() {
+            [] | ["never"] => Ok(Repeat::Never),
+            ["daily"] | ["every", "day"] => Ok(Repeat::Daily),
+            ["weekly"] | ["every", "week"] => Ok(Repeat::Weekly),
+            ["monthly"] | ["every", "month"] => Ok(Repeat::Monthly),
+            ["yearly"] | ["every", "year"] => Ok(Repeat::Yearly),
+            ["every", n, "days" | "day"] => Ok(Repeat::EveryDays(parse_interval(n)?)),
+            ["every", n, "weeks" | "week"] => Ok(Repeat::EveryWeeks {
+                interval: parse_interval(n)?,
+                days: vec![],
+            }),
+            ["every", n, "weeks" | "week", "on", days @ ..] => Ok(Repeat::EveryWeeks {
+                interval: parse_interval(n)?,
+                days: parse_days(&days.join(" "), locale)?,
+            }),
+            ["every", "week", "on", days @ ..] => Ok(Repeat::EveryWeeks {
+                interval: 1,
+                days: parse_days(&days.join(" "), locale)?,
+            }),
+            ["first", "weekday", "of", "the" | "every", "quarter"] => {
+                Ok(Repeat::FirstWeekdayOfQuarter)
+            }
+            [nth, day, "of", "the" | "every", "month"] => Ok(Repeat::NthWeekdayOfMonth {
+                nth: parse_nth(nth)?,
+                day: parse_day(day, locale)?,
+            }),
+            [nth, "to", "last", day, "of", "the" | "every", "month"] => {
+                Ok(Repeat::NthWeekdayOfMonth {
+                    nth: parse_nth_to_last(nth)?,
+                    day: parse_day(day, locale)?,
+                })
+            }
+            [day, "of", "the" | "every", "month"] => {
+                Ok(Repeat::DayOfMonth(parse_day_of_month(day)?))
+            }
+            _ => Ok(Repeat::DaysOfWeek(parse_days(&lower, locale)?)),
+        }
+    }
 
-                Ok(Repeat::DaysOfWeek(days))
+    /// The first date after `date` that the rule falls on, aiming for `anchor` rather
+    /// than for the day and time of `date`. Returns `None` for tasks that don't repeat.
+    ///
+    /// Monthly and yearly repeats fall on the anchor's day, or on the last day of the
+    /// month if it's too short, so a task due on the 31st doesn't stay on the 28th for
+    /// good after February. Every occurrence is due at the anchor's wall-clock time in
+    /// the zone of `date`, unless a DST change skips it on that day.
+    pub fn next_date_from<Tz: TimeZone>(
+        &self,
+        date: &DateTime<Tz>,
+        anchor: &Anchor,
+    ) -> Option<DateTime<Tz>> {
+        let day = date.date_naive();
+        let next_day = match self {
+            Repeat::Never => return None,
+            Repeat::Daily => day + Days::new(1),
+            Repeat::Weekly => day + Days::new(7),
+            Repeat::Monthly => day_of_month(months_from(day).nth(1)?, anchor.day),
+            Repeat::Yearly => day_of_month(months_from(day).nth(12)?, anchor.day),
+            Repeat::RRule(rrule) => {
+                let next = at_time(rrule.next_day(day)?, anchor.time, date);
+                return rrule.includes(&next).then_some(next);
             }
+            Repeat::DaysOfWeek(days) => (1..=7)
+                .map(|i| day + Days::new(i))
+                .find(|d| days.contains(&DayOfWeek::from_chrono(d.weekday())))?,
+            Repeat::EveryDays(n) => day + Days::new((*n).into()),
+            Repeat::EveryWeeks { interval, days } if days.is_empty() => {
+                day + Days::new(7 * u64::from(*interval))
+            }
+            Repeat::EveryWeeks { interval, days } => {
+                let week_start = day - Days::new(day.weekday().num_days_from_monday().into());
+                let rest_of_week = (day.weekday().num_days_from_monday() + 1..7)
+                    .map(|i| week_start + Days::new(i.into()));
+                let next_week_start = week_start + Days::new(7 * u64::from(*interval));
+                let next_week = (0..7).map(|i| next_week_start + Days::new(i));
+                rest_of_week
+                    .chain(next_week)
+                    .find(|d| days.contains(&DayOfWeek::from_chrono(d.weekday())))?
+            }
+            Repeat::NthWeekdayOfMonth { nth, day: weekday } => {
+                // Not every month has a fifth Friday, but one within the next year does
+                months_from(day)
+                    .take(13)
+                    .filter_map(|month| nth_weekday_of_month(month, *nth, weekday))
+                    .find(|d| *d > day)?
+            }
+            Repeat::DayOfMonth(n) => months_from(day)
+                .map(|month| day_of_month(month, *n))
+                .find(|d| *d > day)?,
+            Repeat::FirstWeekdayOfQuarter => {
+                let quarter_start =
+                    NaiveDate::from_ymd_opt(day.year(), (day.month0() / 3) * 3 + 1, 1)?;
+                months_from(quarter_start)
+                    .step_by(3)
+                    .map(first_weekday_of_month)
+                    .find(|d| *d > day)?
+            }
+        };
+        Some(at_time(next_day, anchor.time, date))
+    }
+
+    /// Every date after `date` that the rule falls on, as `next_date_from` finds them one
+    /// after the other, until the rule runs out of occurrences
+    pub fn occurrences<Tz: TimeZone>(
+        &self,
+        date: &DateTime<Tz>,
+        anchor: Anchor,
+    ) -> impl Iterator<Item = DateTime<Tz>> {
+        let first = self
+            .next_date_from(date, &anchor)
+            .map(|next| (self.after_occurrence(), next));
+        std::iter::successors(first, move |(repeat, date)| {
+            let next = repeat.next_date_from(date, &anchor)?;
+            Some((repeat.after_occurrence(), next))
+        })
+        .map(|(_, date)| date)
+    }
+
+    /// Whether the rule falls on the same day of every month
+    pub fn keeps_day_of_month(&self) -> bool {
+        matches!(self, Repeat::Monthly | Repeat::Yearly)
+    }
+
+    /// The rule that applies after moving on to the next occurrence
+    pub fn after_occurrence(&self) -> Repeat {
+        match self {
+            Repeat::RRule(rrule) => Repeat::RRule(rrule.after_occurrence()),
+            _ => self.clone(),
         }
     }
 }
 
+/// The first day of the month of `day` and of every month after it
+fn months_from(day: NaiveDate) -> impl Iterator<Item = NaiveDate> {
+    let first = day.with_day(1).unwrap();
+    (0..).map_while(move |i| first.checked_add_months(Months::new(i)))
+}
+
+pub(crate) fn days_in_month(month: NaiveDate) -> u32 {
+    let next_month = month.with_day(1).unwrap() + Months::new(1);
+    (next_month - Days::new(1)).day()
+}
+
+fn day_of_month(month: NaiveDate, n: u32) -> NaiveDate {
+    month.with_day(n.min(days_in_month(month))).unwrap()
+}
+
+fn nth_weekday_of_month(month: NaiveDate, nth: i8, weekday: &DayOfWeek) -> Option<NaiveDate> {
+    let days: Vec<NaiveDate> = (1..=days_in_month(month))
+        .map(|d| month.with_day(d).unwrap())
+        .filter(|d| DayOfWeek::from_chrono(d.weekday()) == *weekday)
+        .collect();
+    let idx = match nth {
+        1.. => usize::try_from(nth - 1).ok()?,
+        ..=-1 => days.len().checked_sub(usize::from(nth.unsigned_abs()))?,
+        0 => return None,
+    };
+    days.get(idx).copied()
+}
+
+fn first_weekday_of_month(month: NaiveDate) -> NaiveDate {
+    (1..=7)
+        .map(|d| month.with_day(d).unwrap())
+        .find(|d| d.weekday().num_days_from_monday() < 5)
+        .unwrap()
+}
+
+/// `day` at the same wall-clock time as `date`
+pub(crate) fn at_same_time(date: &DateTime<Local>, day: NaiveDate) -> DateTime<Local> {
+    at_time(day, date.time(), date)
+}
+
+/// `day` at the wall-clock `time` in the zone of `previous`. If a DST change skips that
+/// time on that day, the offset of `previous` is used, which lands just after the change.
+fn at_time<Tz: TimeZone>(day: NaiveDate, time: NaiveTime, previous: &DateTime<Tz>) -> DateTime<Tz> {
+    let naive = day.and_time(time);
+    let tz = previous.timezone();
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .unwrap_or_else(|| tz.from_utc_datetime(&(naive - previous.offset().fix())))
+}
+
+fn parse_interval(s: &str) -> Result<u32> {
+    match s.parse() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(anyhow!("Invalid interval '{}'", s)),
+    }
+}
+
+fn parse_days(s: &str, locale: Locale) -> Result<Vec<DayOfWeek>> {
+    s.split(',').map(|s| parse_day(s.trim(), locale)).collect()
+}
+
+fn parse_day(s: &str, locale: Locale) -> Result<DayOfWeek> {
+    locale
+        .parse_weekday(s)
+        .ok_or_else(|| anyhow!("Invalid day of the week '{}'", s))
+}
+
+fn parse_nth(s: &str) -> Result<i8> {
+    match s {
+        "first" | "1st" => Ok(1),
+        "second" | "2nd" => Ok(2),
+        "third" | "3rd" => Ok(3),
+        "fourth" | "4th" => Ok(4),
+        "fifth" | "5th" => Ok(5),
+        "last" => Ok(-1),
+        _ => Err(anyhow!("Invalid position '{}'", s)),
+    }
+}
+
+/// Parses the `nth` of "nth to last", which counts back from the end of the month
+fn parse_nth_to_last(s: &str) -> Result<i8> {
+    match parse_nth(s)? {
+        n if n > 0 => Ok(-n),
+        _ => Err(anyhow!("Invalid position '{} to last'", s)),
+    }
+}
+
+fn parse_day_of_month(s: &str) -> Result<u32> {
+    let digits = s.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    match digits.parse() {
+        Ok(n) if (1..=31).contains(&n) && (digits == s || ordinal(n) == s) => Ok(n),
+        _ => Err(anyhow!("Invalid day of the month '{}'", s)),
+    }
+}
+
+fn ordinal(n: u32) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
 impl Display for Repeat {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -53,6 +364,271 @@ impl Display for Repeat {
                 let days = days.iter().map(|d| d.to_string()).join(",");
                 write!(f, "{}", days)
             }
+            Repeat::EveryDays(1) => write!(f, "Every day"),
+            Repeat::EveryDays(n) => write!(f, "Every {} days", n),
+            Repeat::EveryWeeks { interval, days } => {
+                match interval {
+                    1 => write!(f, "Every week")?,
+                    n => write!(f, "Every {} weeks", n)?,
+                }
+                if !days.is_empty() {
+                    let days = days.iter().map(|d| d.to_string()).join(",");
+                    write!(f, " on {}", days)?;
+                }
+                Ok(())
+            }
+            Repeat::NthWeekdayOfMonth { nth, day } => {
+                let nth = match nth {
+                    -1 => "Last".to_string(),
+                    ..=-2 => format!("{} to last", ordinal(u32::from(nth.unsigned_abs()))),
+                    n => ordinal(u32::from(n.unsigned_abs())),
+                };
+                write!(f, "{} {} of the month", nth, day)
+            }
+            Repeat::DayOfMonth(n) => write!(f, "{} of the month", ordinal(*n)),
+            Repeat::FirstWeekdayOfQuarter => write!(f, "First weekday of the quarter"),
+            Repeat::RRule(rrule) => write!(f, "{}", rrule),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use DayOfWeek::*;
+
+    fn nth(nth: i8, day: DayOfWeek) -> Repeat {
+        Repeat::NthWeekdayOfMonth { nth, day }
+    }
+
+    #[test]
+    fn parse_and_display_round_trip() {
+        let cases = [
+            ("never", Repeat::Never, "Never"),
+            ("daily", Repeat::Daily, "Daily"),
+            ("every week", Repeat::Weekly, "Weekly"),
+            ("Monthly", Repeat::Monthly, "Monthly"),
+            ("yearly", Repeat::Yearly, "Yearly"),
+            (
+                "mon, wed",
+                Repeat::DaysOfWeek(vec![Monday, Wednesday]),
+                "Mon,Wed",
+            ),
+            ("every 3 days", Repeat::EveryDays(3), "Every 3 days"),
+            (
+                "every 2 weeks",
+                Repeat::EveryWeeks {
+                    interval: 2,
+                    days: vec![],
+                },
+                "Every 2 weeks",
+            ),
+            (
+                "every 2 weeks on tue,thu",
+                Repeat::EveryWeeks {
+                    interval: 2,
+                    days: vec![Tuesday, Thursday],
+                },
+                "Every 2 weeks on Tue,Thu",
+            ),
+            (
+                "first monday of the month",
+                nth(1, Monday),
+                "1st Mon of the month",
+            ),
+            (
+                "last fri of every month",
+                nth(-1, Friday),
+                "Last Fri of the month",
+            ),
+            (
+                "2nd to last fri of the month",
+                nth(-2, Friday),
+                "2nd to last Fri of the month",
+            ),
+            (
+                "third to last sunday of the month",
+                nth(-3, Sunday),
+                "3rd to last Sun of the month",
+            ),
+            (
+                "31st of the month",
+                Repeat::DayOfMonth(31),
+                "31st of the month",
+            ),
+            (
+                "12 of every month",
+                Repeat::DayOfMonth(12),
+                "12th of the month",
+            ),
+            (
+                "first weekday of the quarter",
+                Repeat::FirstWeekdayOfQuarter,
+                "First weekday of the quarter",
+            ),
+            (
+                "FREQ=MONTHLY;BYDAY=-2FR",
+                Repeat::RRule("FREQ=MONTHLY;BYDAY=-2FR".parse().unwrap()),
+                "FREQ=MONTHLY;BYDAY=-2FR",
+            ),
+        ];
+
+        for (input, expected, display) in cases {
+            let parsed = Repeat::parse_from_str(input, Locale::English).unwrap();
+            assert_eq!(parsed, expected, "parsing '{}'", input);
+            assert_eq!(parsed.to_string(), display, "displaying '{}'", input);
+            let reparsed = Repeat::parse_from_str(display, Locale::English).unwrap();
+            assert_eq!(reparsed, expected, "parsing '{}' again", display);
+        }
+    }
+
+    #[test]
+    fn intervals_of_one_are_singular() {
+        let cases = [
+            (Repeat::EveryDays(1), "Every day"),
+            (
+                Repeat::EveryWeeks {
+                    interval: 1,
+                    days: vec![],
+                },
+                "Every week",
+            ),
+            (
+                Repeat::EveryWeeks {
+                    interval: 1,
+                    days: vec![Monday, Friday],
+                },
+                "Every week on Mon,Fri",
+            ),
+        ];
+        for (repeat, display) in cases {
+            assert_eq!(repeat.to_string(), display);
+            let reparsed = Repeat::parse_from_str(display, Locale::English).unwrap();
+            // "Every day" reads back as Daily, which falls on the same dates
+            let date = Utc.with_ymd_and_hms(2026, 1, 1, 9, 0, 0).unwrap();
+            assert_eq!(
+                reparsed.next_date_from(&date, &Anchor::of(&date)),
+                repeat.next_date_from(&date, &Anchor::of(&date)),
+                "'{}'",
+                display
+            );
+        }
+    }
+
+    /// The next `n` dates after `start`, as a task that is completed on time would move
+    fn next_dates(repeat: &Repeat, start: &str, n: usize) -> Vec<NaiveDate> {
+        let start: NaiveDate = start.parse().unwrap();
+        let mut date = Utc.from_utc_datetime(&start.and_hms_opt(9, 0, 0).unwrap());
+        let anchor = Anchor::of(&date);
+        (0..n)
+            .map(|_| {
+                date = repeat.next_date_from(&date, &anchor).unwrap();
+                assert_eq!(date.time(), anchor.time);
+                date.date_naive()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn next_dates_from() {
+        let every_weeks = |interval, days| Repeat::EveryWeeks { interval, days };
+        // 2026-01-01 is a Thursday
+        let cases = [
+            (
+                every_weeks(3, vec![]),
+                "2026-01-01",
+                vec!["2026-01-22", "2026-02-12", "2026-03-05"],
+            ),
+            (
+                every_weeks(2, vec![Tuesday, Thursday]),
+                "2026-01-01",
+                vec!["2026-01-13", "2026-01-15", "2026-01-27"],
+            ),
+            (
+                every_weeks(2, vec![Monday, Friday]),
+                "2026-01-05",
+                vec!["2026-01-09", "2026-01-19", "2026-01-23", "2026-02-02"],
+            ),
+            (
+                nth(1, Monday),
+                "2026-01-01",
+                vec!["2026-01-05", "2026-02-02", "2026-03-02"],
+            ),
+            (
+                nth(-1, Friday),
+                "2026-01-01",
+                vec!["2026-01-30", "2026-02-27", "2026-03-27"],
+            ),
+            (
+                nth(-2, Friday),
+                "2026-01-01",
+                vec!["2026-01-23", "2026-02-20", "2026-03-20"],
+            ),
+            // Months without a fifth Friday are skipped
+            (
+                nth(5, Friday),
+                "2026-01-01",
+                vec!["2026-01-30", "2026-05-29", "2026-07-31"],
+            ),
+            // Short months fall on their last day, without losing the 31st after them
+            (
+                Repeat::DayOfMonth(31),
+                "2026-01-01",
+                vec![
+                    "2026-01-31",
+                    "2026-02-28",
+                    "2026-03-31",
+                    "2026-04-30",
+                    "2026-05-31",
+                ],
+            ),
+            (
+                Repeat::DayOfMonth(30),
+                "2028-02-01",
+                vec!["2028-02-29", "2028-03-30", "2028-04-30"],
+            ),
+            (
+                Repeat::FirstWeekdayOfQuarter,
+                "2026-02-10",
+                vec!["2026-04-01", "2026-07-01", "2026-10-01"],
+            ),
+            // The first of January, April and July 2028 are Saturdays
+            (
+                Repeat::FirstWeekdayOfQuarter,
+                "2027-12-15",
+                vec!["2028-01-03", "2028-04-03", "2028-07-03"],
+            ),
+        ];
+        for (repeat, start, expected) in cases {
+            let expected: Vec<NaiveDate> = expected.iter().map(|d| d.parse().unwrap()).collect();
+            assert_eq!(
+                next_dates(&repeat, start, expected.len()),
+                expected,
+                "{} from {}",
+                repeat,
+                start
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        let cases = [
+            "every 0 days",
+            "every -1 weeks",
+            "sixth mon of the month",
+            "last to last fri of the month",
+            "32nd of the month",
+            "2th of the month",
+            "funday",
+        ];
+        for input in cases {
+            assert!(
+                Repeat::parse_from_str(input, Locale::English).is_err(),
+                "'{}' should not parse",
+                input
+            );
         }
     }
 }
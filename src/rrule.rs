@@ -0,0 +1,676 @@
+use crate::{day_of_week::DayOfWeek, repeat};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Datelike, Days, Local, Months, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+
+/// How far ahead to look for the next occurrence before deciding that there is none,
+/// like for the 30th of February
+const SEARCH_YEARS: u32 = 10;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// A day in `BYDAY`, like `MO`, or `-1FR` for the last Friday of the month or year
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WeekdayNum {
+    pub nth: Option<i8>,
+    pub day: DayOfWeek,
+}
+
+/// An iCalendar (RFC 5545) recurrence rule, like `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE`.
+///
+/// Tasks don't have a separate start date, so the parts a rule leaves out, like the
+/// day of the month of a monthly rule, are taken from the due date. `COUNT` is the
+/// number of occurrences left, including the current one.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct RRule {
+    pub freq: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<DateTime<Local>>,
+    pub by_month: Vec<u32>,
+    pub by_month_day: Vec<i8>,
+    pub by_day: Vec<WeekdayNum>,
+    pub by_set_pos: Vec<i16>,
+    pub week_start: DayOfWeek,
+}
+
+impl RRule {
+    /// The first day after `day` that the rule falls on. Returns `None` once the rule
+    /// runs out of occurrences, except for `UNTIL`, which depends on the time of day.
+    pub fn next_day(&self, day: NaiveDate) -> Option<NaiveDate> {
+        if self.count.is_some_and(|count| count <= 1) {
+            return None;
+        }
+
+        let last_day = day.checked_add_months(Months::new(12 * SEARCH_YEARS))?;
+        self.periods(day)
+            .take_while(|start| *start <= last_day)
+            .flat_map(|start| self.occurrences_in(start, day))
+            .find(|d| *d > day)
+    }
+
+    /// Whether an occurrence due at `date` is within `UNTIL`
+    pub fn includes<Tz: TimeZone>(&self, date: &DateTime<Tz>) -> bool {
+        self.until.is_none_or(|until| *date <= until)
+    }
+
+    /// The rule that applies after moving on to the next occurrence
+    pub fn after_occurrence(&self) -> RRule {
+        RRule {
+            count: self.count.map(|count| count.saturating_sub(1)),
+            ..self.clone()
+        }
+    }
+
+    /// The first day of the period (day, week, month or year) that `day` is in, and of
+    /// every `interval`th period after it
+    fn periods(&self, day: NaiveDate) -> impl Iterator<Item = NaiveDate> {
+        let first = match self.freq {
+            Frequency::Daily => day,
+            Frequency::Weekly => {
+                let week_start = self.week_start.to_int() - 1;
+                let offset = (day.weekday().num_days_from_monday() + 7 - week_start) % 7;
+                day - Days::new(offset.into())
+            }
+            Frequency::Monthly => day.with_day(1).unwrap(),
+            Frequency::Yearly => day.with_ordinal(1).unwrap(),
+        };
+        let freq = self.freq;
+        let interval = self.interval;
+        (0u32..).map_while(move |i| {
+            let n = i.checked_mul(interval)?;
+            match freq {
+                Frequency::Daily => first.checked_add_days(Days::new(n.into())),
+                Frequency::Weekly => first.checked_add_days(Days::new(7 * u64::from(n))),
+                Frequency::Monthly => first.checked_add_months(Months::new(n)),
+                Frequency::Yearly => first.checked_add_months(Months::new(n.checked_mul(12)?)),
+            }
+        })
+    }
+
+    /// The days of the period starting at `start` that the rule falls on, in order
+    fn occurrences_in(&self, start: NaiveDate, anchor: NaiveDate) -> Vec<NaiveDate> {
+        let end = match self.freq {
+            Frequency::Daily => start + Days::new(1),
+            Frequency::Weekly => start + Days::new(7),
+            Frequency::Monthly => start + Months::new(1),
+            Frequency::Yearly => start + Months::new(12),
+        };
+        let days: Vec<NaiveDate> = start
+            .iter_days()
+            .take_while(|d| *d < end)
+            .filter(|d| self.matches(*d, anchor))
+            .collect();
+
+        if self.by_set_pos.is_empty() {
+            return days;
+        }
+        self.by_set_pos
+            .iter()
+            .filter_map(|pos| {
+                let idx = match *pos {
+                    1.. => usize::try_from(pos - 1).ok()?,
+                    _ => days.len().checked_sub(usize::from(pos.unsigned_abs()))?,
+                };
+                days.get(idx).copied()
+            })
+            .sorted()
+            .dedup()
+            .collect()
+    }
+
+    fn matches(&self, day: NaiveDate, anchor: NaiveDate) -> bool {
+        let by_day_or_month_day = !self.by_day.is_empty() || !self.by_month_day.is_empty();
+
+        let month_matches = match self.freq {
+            _ if !self.by_month.is_empty() => self.by_month.contains(&day.month()),
+            Frequency::Yearly if !by_day_or_month_day => day.month() == anchor.month(),
+            _ => true,
+        };
+
+        let month_day_matches = match self.freq {
+            _ if !self.by_month_day.is_empty() => {
+                let days_in_month = i16::try_from(repeat::days_in_month(day)).unwrap();
+                self.by_month_day.iter().any(|n| {
+                    let n = i16::from(*n);
+                    let n = if n < 0 { days_in_month + n + 1 } else { n };
+                    n == i16::try_from(day.day()).unwrap()
+                })
+            }
+            Frequency::Monthly | Frequency::Yearly if self.by_day.is_empty() => {
+                day.day() == anchor.day()
+            }
+            _ => true,
+        };
+
+        let day_matches = match self.freq {
+            _ if !self.by_day.is_empty() => {
+                self.by_day.iter().any(|d| self.weekday_matches(d, day))
+            }
+            Frequency::Weekly => day.weekday() == anchor.weekday(),
+            _ => true,
+        };
+
+        month_matches && month_day_matches && day_matches
+    }
+
+    fn weekday_matches(&self, weekday: &WeekdayNum, day: NaiveDate) -> bool {
+        if DayOfWeek::from_chrono(day.weekday()) != weekday.day {
+            return false;
+        }
+        let Some(nth) = weekday.nth else {
+            return true;
+        };
+
+        // The position counts within the month, unless a yearly rule isn't limited to
+        // some months
+        let (idx, len) = match self.freq {
+            Frequency::Yearly if self.by_month.is_empty() => {
+                let days_in_year = NaiveDate::from_ymd_opt(day.year(), 12, 31)
+                    .unwrap()
+                    .ordinal();
+                (day.ordinal0(), days_in_year)
+            }
+            _ => (day.day0(), repeat::days_in_month(day)),
+        };
+        let nth = i32::from(nth);
+        if nth > 0 {
+            i32::try_from(idx / 7 + 1).unwrap() == nth
+        } else {
+            i32::try_from((len - 1 - idx) / 7 + 1).unwrap() == -nth
+        }
+    }
+}
+
+impl FromStr for RRule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<RRule> {
+        let upper = s.trim().to_uppercase();
+        let rule = upper.strip_prefix("RRULE:").unwrap_or(&upper);
+
+        let mut parts = vec![];
+        for part in rule.split(';').filter(|p| !p.is_empty()) {
+            let (name, value) = part
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Invalid rule part '{}'", part))?;
+            if parts.iter().any(|(n, _)| *n == name) {
+                bail!("{} is given more than once", name);
+            }
+            parts.push((name, value));
+        }
+
+        let mut freq = None;
+        let mut rrule = RRule {
+            freq: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_month: vec![],
+            by_month_day: vec![],
+            by_day: vec![],
+            by_set_pos: vec![],
+            week_start: DayOfWeek::Monday,
+        };
+        for (name, value) in parts {
+            match name {
+                "FREQ" => freq = Some(parse_freq(value)?),
+                "INTERVAL" => rrule.interval = parse_number(name, value, 1..=u32::MAX)?,
+                "COUNT" => rrule.count = Some(parse_number(name, value, 1..=u32::MAX)?),
+                "UNTIL" => rrule.until = Some(parse_until(value)?),
+                "BYMONTH" => rrule.by_month = parse_list(name, value, 1..=12)?,
+                "BYMONTHDAY" => rrule.by_month_day = parse_list(name, value, -31..=31)?,
+                "BYSETPOS" => rrule.by_set_pos = parse_list(name, value, -366..=366)?,
+                "BYDAY" => {
+                    rrule.by_day = value
+                        .split(',')
+                        .map(parse_weekday_num)
+                        .collect::<Result<_>>()?
+                }
+                "WKST" => rrule.week_start = parse_ical_day(value)?,
+                "BYYEARDAY" | "BYWEEKNO" | "BYHOUR" | "BYMINUTE" | "BYSECOND" => {
+                    bail!("{} is not supported", name)
+                }
+                _ => bail!("Unknown rule part '{}'", name),
+            }
+        }
+        rrule.freq = freq.ok_or_else(|| anyhow!("The rule has no FREQ"))?;
+
+        if rrule.count.is_some() && rrule.until.is_some() {
+            bail!("COUNT and UNTIL can't be used together");
+        }
+        if rrule.freq == Frequency::Weekly && !rrule.by_month_day.is_empty() {
+            bail!("BYMONTHDAY can't be used with FREQ=WEEKLY");
+        }
+        let nth_limit = match rrule.freq {
+            Frequency::Daily | Frequency::Weekly => 0,
+            Frequency::Yearly if rrule.by_month.is_empty() => 53,
+            Frequency::Monthly | Frequency::Yearly => 5,
+        };
+        let out_of_range = |d: &WeekdayNum| d.nth.is_some_and(|n| n.unsigned_abs() > nth_limit);
+        if rrule.by_day.iter().any(out_of_range) {
+            match nth_limit {
+                0 => bail!("Numbered days in BYDAY need FREQ=MONTHLY or FREQ=YEARLY"),
+                _ => bail!(
+                    "Numbered days in BYDAY must be between -{0} and {0}",
+                    nth_limit
+                ),
+            }
+        }
+        let by_parts = !rrule.by_month.is_empty()
+            || !rrule.by_month_day.is_empty()
+            || !rrule.by_day.is_empty();
+        if !rrule.by_set_pos.is_empty() && !by_parts {
+            bail!("BYSETPOS needs BYMONTH, BYMONTHDAY or BYDAY");
+        }
+
+        Ok(rrule)
+    }
+}
+
+impl TryFrom<String> for RRule {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<RRule> {
+        s.parse()
+    }
+}
+
+impl From<RRule> for String {
+    fn from(rrule: RRule) -> String {
+        rrule.to_string()
+    }
+}
+
+fn parse_freq(s: &str) -> Result<Frequency> {
+    match s {
+        "DAILY" => Ok(Frequency::Daily),
+        "WEEKLY" => Ok(Frequency::Weekly),
+        "MONTHLY" => Ok(Frequency::Monthly),
+        "YEARLY" => Ok(Frequency::Yearly),
+        "SECONDLY" | "MINUTELY" | "HOURLY" => bail!("FREQ={} is not supported", s),
+        _ => bail!("Invalid FREQ '{}'", s),
+    }
+}
+
+fn parse_number<T>(name: &str, s: &str, range: std::ops::RangeInclusive<T>) -> Result<T>
+where
+    T: FromStr + PartialOrd + Default,
+{
+    match s.parse::<T>() {
+        Ok(n) if range.contains(&n) && n != T::default() => Ok(n),
+        _ => Err(anyhow!("Invalid {} '{}'", name, s)),
+    }
+}
+
+fn parse_list<T>(name: &str, s: &str, range: std::ops::RangeInclusive<T>) -> Result<Vec<T>>
+where
+    T: FromStr + PartialOrd + Default + Clone,
+{
+    s.split(',')
+        .map(|n| parse_number(name, n, range.clone()))
+        .collect()
+}
+
+fn parse_until(s: &str) -> Result<DateTime<Local>> {
+    let invalid = || anyhow!("Invalid UNTIL '{}'", s);
+    if let Some(utc) = s.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").map_err(|_| invalid())?;
+        return Ok(Utc.from_utc_datetime(&naive).with_timezone(&Local));
+    }
+
+    // A date without a time includes the whole day
+    let naive = match NaiveDateTime::parse_from_str(s, "%Y%m%dT%H%M%S") {
+        Ok(naive) => naive,
+        Err(_) => NaiveDate::parse_from_str(s, "%Y%m%d")
+            .map_err(|_| invalid())?
+            .and_hms_opt(23, 59, 59)
+            .unwrap(),
+    };
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .ok_or_else(invalid)
+}
+
+fn parse_weekday_num(s: &str) -> Result<WeekdayNum> {
+    if !s.is_ascii() {
+        bail!("Invalid day '{}'", s);
+    }
+    let (nth, day) = s.split_at(s.len().saturating_sub(2));
+    let nth = match nth {
+        "" => None,
+        _ => Some(parse_number("BYDAY", nth, -53..=53).context("Invalid BYDAY")?),
+    };
+    Ok(WeekdayNum {
+        nth,
+        day: parse_ical_day(day)?,
+    })
+}
+
+fn parse_ical_day(s: &str) -> Result<DayOfWeek> {
+    match s {
+        "MO" => Ok(DayOfWeek::Monday),
+        "TU" => Ok(DayOfWeek::Tuesday),
+        "WE" => Ok(DayOfWeek::Wednesday),
+        "TH" => Ok(DayOfWeek::Thursday),
+        "FR" => Ok(DayOfWeek::Friday),
+        "SA" => Ok(DayOfWeek::Saturday),
+        "SU" => Ok(DayOfWeek::Sunday),
+        _ => bail!("Invalid day '{}'", s),
+    }
+}
+
+fn ical_day(day: &DayOfWeek) -> &'static str {
+    match day {
+        DayOfWeek::Monday => "MO",
+        DayOfWeek::Tuesday => "TU",
+        DayOfWeek::Wednesday => "WE",
+        DayOfWeek::Thursday => "TH",
+        DayOfWeek::Friday => "FR",
+        DayOfWeek::Saturday => "SA",
+        DayOfWeek::Sunday => "SU",
+    }
+}
+
+impl Display for RRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let freq = match self.freq {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        };
+        let mut parts = vec![format!("FREQ={}", freq)];
+        if self.interval != 1 {
+            parts.push(format!("INTERVAL={}", self.interval));
+        }
+        if let Some(count) = self.count {
+            parts.push(format!("COUNT={}", count));
+        }
+        if let Some(until) = self.until {
+            parts.push(format!(
+                "UNTIL={}",
+                until.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ")
+            ));
+        }
+        if !self.by_month.is_empty() {
+            parts.push(format!("BYMONTH={}", self.by_month.iter().join(",")));
+        }
+        if !self.by_month_day.is_empty() {
+            parts.push(format!("BYMONTHDAY={}", self.by_month_day.iter().join(",")));
+        }
+        if !self.by_day.is_empty() {
+            let mut days = self.by_day.iter().map(|d| {
+                let nth = d.nth.map(|n| n.to_string()).unwrap_or_default();
+                format!("{}{}", nth, ical_day(&d.day))
+            });
+            parts.push(format!("BYDAY={}", days.join(",")));
+        }
+        if !self.by_set_pos.is_empty() {
+            parts.push(format!("BYSETPOS={}", self.by_set_pos.iter().join(",")));
+        }
+        if self.week_start != DayOfWeek::Monday {
+            parts.push(format!("WKST={}", ical_day(&self.week_start)));
+        }
+        write!(f, "{}", parts.join(";"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    /// Up to `n` of the days the rule falls on after `start`, moving on to the next
+    /// occurrence each time like a completed task does
+    fn next_days(rule: &str, start: &str, n: usize) -> Vec<NaiveDate> {
+        let mut rrule: RRule = rule.parse().unwrap();
+        let mut day = date(start);
+        let mut days = vec![];
+        while days.len() < n {
+            let Some(next) = rrule.next_day(day) else {
+                break;
+            };
+            let at_noon = Local
+                .from_local_datetime(&next.and_hms_opt(12, 0, 0).unwrap())
+                .earliest()
+                .unwrap();
+            if !rrule.includes(&at_noon) {
+                break;
+            }
+            days.push(next);
+            day = next;
+            rrule = rrule.after_occurrence();
+        }
+        days
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        let cases = [
+            "",
+            "FREQDAILY",
+            "INTERVAL=2",
+            "FREQ=FORTNIGHTLY",
+            "FREQ=HOURLY",
+            "FREQ=DAILY;FREQ=WEEKLY",
+            "FREQ=DAILY;INTERVAL=0",
+            "FREQ=DAILY;COUNT=-1",
+            "FREQ=DAILY;COUNT=2;UNTIL=20270101",
+            "FREQ=DAILY;UNTIL=2027",
+            "FREQ=WEEKLY;BYMONTHDAY=1",
+            "FREQ=WEEKLY;BYDAY=1MO",
+            "FREQ=MONTHLY;BYDAY=6MO",
+            "FREQ=MONTHLY;BYDAY=XX",
+            "FREQ=MONTHLY;BYMONTHDAY=0",
+            "FREQ=MONTHLY;BYMONTHDAY=32",
+            "FREQ=YEARLY;BYMONTH=13",
+            "FREQ=YEARLY;BYDAY=54MO",
+            "FREQ=DAILY;BYSETPOS=1",
+            "FREQ=YEARLY;BYWEEKNO=1",
+            "FREQ=DAILY;WKST=XX",
+            "FREQ=DAILY;COLOR=RED",
+        ];
+        for rule in cases {
+            assert!(
+                rule.parse::<RRule>().is_err(),
+                "'{}' should not parse",
+                rule
+            );
+        }
+    }
+
+    #[test]
+    fn serialize_round_trip() {
+        let cases = [
+            ("FREQ=DAILY", "FREQ=DAILY"),
+            ("rrule:freq=weekly;byday=mo,we", "FREQ=WEEKLY;BYDAY=MO,WE"),
+            ("FREQ=WEEKLY;INTERVAL=1", "FREQ=WEEKLY"),
+            ("BYDAY=FR;FREQ=MONTHLY", "FREQ=MONTHLY;BYDAY=FR"),
+            ("FREQ=MONTHLY;BYDAY=-1FR", "FREQ=MONTHLY;BYDAY=-1FR"),
+            (
+                "FREQ=MONTHLY;BYMONTHDAY=-1,15",
+                "FREQ=MONTHLY;BYMONTHDAY=-1,15",
+            ),
+            (
+                "FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=29",
+                "FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=29",
+            ),
+            (
+                "FREQ=MONTHLY;BYSETPOS=-1;BYDAY=MO,TU,WE,TH,FR",
+                "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1",
+            ),
+            (
+                "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,SU;WKST=SU",
+                "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,SU;WKST=SU",
+            ),
+            ("FREQ=WEEKLY;WKST=MO", "FREQ=WEEKLY"),
+            ("FREQ=DAILY;COUNT=5", "FREQ=DAILY;COUNT=5"),
+            (
+                "FREQ=DAILY;UNTIL=20270101T120000Z",
+                "FREQ=DAILY;UNTIL=20270101T120000Z",
+            ),
+        ];
+        for (input, expected) in cases {
+            let rrule: RRule = input.parse().unwrap();
+            assert_eq!(rrule.to_string(), expected, "serializing '{}'", input);
+            assert_eq!(expected.parse::<RRule>().unwrap(), rrule);
+
+            let json = serde_json::to_string(&rrule).unwrap();
+            assert_eq!(json, format!("\"{}\"", expected));
+            assert_eq!(serde_json::from_str::<RRule>(&json).unwrap(), rrule);
+        }
+
+        // A date-only UNTIL is the end of that day here, so it's written in UTC
+        let rrule: RRule = "FREQ=DAILY;UNTIL=20270101".parse().unwrap();
+        assert_eq!(rrule.to_string().parse::<RRule>().unwrap(), rrule);
+    }
+
+    #[test]
+    fn next_occurrences() {
+        // 2026-01-01 is a Thursday
+        let cases = [
+            (
+                "FREQ=DAILY;INTERVAL=3",
+                "2026-01-01",
+                ["2026-01-04", "2026-01-07", "2026-01-10"],
+            ),
+            (
+                "FREQ=WEEKLY",
+                "2026-01-01",
+                ["2026-01-08", "2026-01-15", "2026-01-22"],
+            ),
+            (
+                "FREQ=WEEKLY;BYDAY=MO,WE,FR",
+                "2026-01-01",
+                ["2026-01-02", "2026-01-05", "2026-01-07"],
+            ),
+            (
+                "FREQ=WEEKLY;INTERVAL=2;BYDAY=TU,TH",
+                "2026-01-01",
+                ["2026-01-13", "2026-01-15", "2026-01-27"],
+            ),
+            // The week start decides which Sunday goes with which Monday
+            (
+                "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,SU",
+                "2026-01-04",
+                ["2026-01-12", "2026-01-18", "2026-01-26"],
+            ),
+            (
+                "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,SU;WKST=SU",
+                "2026-01-04",
+                ["2026-01-05", "2026-01-18", "2026-01-19"],
+            ),
+            (
+                "FREQ=MONTHLY",
+                "2026-01-15",
+                ["2026-02-15", "2026-03-15", "2026-04-15"],
+            ),
+            (
+                "FREQ=MONTHLY;BYDAY=2TU",
+                "2026-01-01",
+                ["2026-01-13", "2026-02-10", "2026-03-10"],
+            ),
+            (
+                "FREQ=MONTHLY;BYDAY=-1FR",
+                "2026-01-01",
+                ["2026-01-30", "2026-02-27", "2026-03-27"],
+            ),
+            (
+                "FREQ=MONTHLY;BYMONTHDAY=1,15",
+                "2026-01-01",
+                ["2026-01-15", "2026-02-01", "2026-02-15"],
+            ),
+            (
+                "FREQ=MONTHLY;BYMONTHDAY=-1",
+                "2026-01-01",
+                ["2026-01-31", "2026-02-28", "2026-03-31"],
+            ),
+            // Months without a 31st are skipped rather than moved to the 30th
+            (
+                "FREQ=MONTHLY;BYMONTHDAY=31",
+                "2026-01-01",
+                ["2026-01-31", "2026-03-31", "2026-05-31"],
+            ),
+            (
+                "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1",
+                "2026-01-01",
+                ["2026-01-30", "2026-02-27", "2026-03-31"],
+            ),
+            (
+                "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=1,2",
+                "2026-01-01",
+                ["2026-01-02", "2026-02-02", "2026-02-03"],
+            ),
+            (
+                "FREQ=YEARLY",
+                "2026-03-15",
+                ["2027-03-15", "2028-03-15", "2029-03-15"],
+            ),
+            (
+                "FREQ=YEARLY;BYMONTH=1,7",
+                "2026-01-01",
+                ["2026-07-01", "2027-01-01", "2027-07-01"],
+            ),
+            (
+                "FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=29",
+                "2026-01-01",
+                ["2028-02-29", "2032-02-29", "2036-02-29"],
+            ),
+            (
+                "FREQ=YEARLY;BYDAY=1MO",
+                "2026-01-01",
+                ["2026-01-05", "2027-01-04", "2028-01-03"],
+            ),
+            (
+                "FREQ=YEARLY;BYMONTH=11;BYDAY=4TH",
+                "2026-01-01",
+                ["2026-11-26", "2027-11-25", "2028-11-23"],
+            ),
+        ];
+        for (rule, start, expected) in cases {
+            let expected = expected.map(date);
+            assert_eq!(
+                next_days(rule, start, 3),
+                expected,
+                "{} from {}",
+                rule,
+                start
+            );
+        }
+    }
+
+    #[test]
+    fn impossible_rules_have_no_next_day() {
+        let rrule: RRule = "FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30".parse().unwrap();
+        assert_eq!(rrule.next_day(date("2026-01-01")), None);
+    }
+
+    #[test]
+    fn count_and_until_end_the_series() {
+        // COUNT includes the current occurrence
+        let days = next_days("FREQ=DAILY;COUNT=3", "2026-01-01", 10);
+        assert_eq!(days, [date("2026-01-02"), date("2026-01-03")]);
+        assert!(next_days("FREQ=DAILY;COUNT=1", "2026-01-01", 10).is_empty());
+
+        // A date-only UNTIL includes that day
+        let days = next_days("FREQ=WEEKLY;UNTIL=20260122", "2026-01-01", 10);
+        let expected = ["2026-01-08", "2026-01-15", "2026-01-22"].map(date);
+        assert_eq!(days, expected);
+        let days = next_days("FREQ=WEEKLY;UNTIL=20260121", "2026-01-01", 10);
+        assert_eq!(days, [date("2026-01-08"), date("2026-01-15")]);
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use super::{insert_new, task_id, TaskStore};
+use crate::{
+    app::Id,
+    error::StorageError,
+    task::Task,
+    utils::{self, FileStamp},
+};
+
+/// Stores all the tasks in a single JSON object keyed by id. Every change rewrites the
+/// whole file.
+///
+/// Other processes may write to the same file, so every change is made under a lock and
+/// applied on top of whatever is on disk at that point.
+pub struct JsonStore {
+    path: PathBuf,
+    tasks: HashMap<Id, Task>,
+    /// The version of the file `tasks` was read from or last written to
+    stamp: Option<FileStamp>,
+    /// Whether a change was applied on top of changes from another process that `load`
+    /// hasn't returned yet
+    merged: bool,
+}
+
+impl JsonStore {
+    /// Opens the file at `path`, creating an empty database if it doesn't exist
+    pub fn open(path: PathBuf) -> Result<Self, StorageError> {
+        let mut store = Self {
+            path,
+            tasks: HashMap::new(),
+            stamp: None,
+            merged: false,
+        };
+        if !store.path.exists() {
+            utils::ensure_parent_dir(&store.path)?;
+            let _lock = utils::lock_file(&store.path, true)?;
+            store.save()?;
+        }
+        Ok(store)
+    }
+
+    fn save(&mut self) -> Result<(), StorageError> {
+        utils::save_tasks(&self.path, &self.tasks)?;
+        self.stamp = utils::file_stamp(&self.path)?;
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<(), StorageError> {
+        self.tasks = utils::load_tasks(&self.path)?;
+        self.stamp = utils::file_stamp(&self.path)?;
+        Ok(())
+    }
+
+    /// Applies `change` to the latest version of the file and saves it
+    fn modify<T>(
+        &mut self,
+        change: impl FnOnce(&mut HashMap<Id, Task>) -> T,
+    ) -> Result<T, StorageError> {
+        let _lock = utils::lock_file(&self.path, true)?;
+        if utils::file_stamp(&self.path)? != self.stamp {
+            self.read()?;
+            self.merged = true;
+        }
+        let result = change(&mut self.tasks);
+        self.save()?;
+        Ok(result)
+    }
+}
+
+impl TaskStore for JsonStore {
+    fn load(&mut self) -> Result<HashMap<Id, Task>, StorageError> {
+        let mut lock = utils::lock_file(&self.path, false)?;
+        // Files from older versions are rewritten when they are read
+        if utils::tasks_need_upgrade(&self.path)? {
+            drop(lock);
+            lock = utils::lock_file(&self.path, true)?;
+        }
+        self.read()?;
+        drop(lock);
+        self.merged = false;
+        Ok(self.tasks.clone())
+    }
+
+    fn insert(&mut self, task: &Task) -> Result<Id, StorageError> {
+        self.modify(|tasks| insert_new(tasks, task))
+    }
+
+    fn update(&mut self, task: &Task) -> Result<(), StorageError> {
+        self.modify(|tasks| {
+            tasks.insert(task_id(task), task.clone());
+        })
+    }
+
+    fn delete(&mut self, id: Id) -> Result<(), StorageError> {
+        self.modify(|tasks| {
+            tasks.remove(&id);
+        })
+    }
+
+    fn list(&self) -> Result<Vec<Task>, StorageError> {
+        Ok(self.tasks.values().cloned().collect())
+    }
+
+    fn has_changed(&self) -> Result<bool, StorageError> {
+        Ok(self.merged || utils::file_stamp(&self.path)? != self.stamp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn old_files_are_upgraded_on_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tasks.json");
+        let v1 = r#"{"1": {"id": 1, "name": "a", "date": "2023-03-01T10:00:00+01:00",
+            "repeats": "Never", "group": null, "description": null, "url": null,
+            "complete": false}}"#;
+        fs::write(&path, v1).unwrap();
+
+        let mut store = JsonStore::open(path.clone()).unwrap();
+        let tasks = store.load().unwrap();
+        assert_eq!(tasks[&1].name, "a");
+        assert!(!utils::tasks_need_upgrade(&path).unwrap());
+        assert!(!store.has_changed().unwrap());
+    }
+
+    #[test]
+    fn changes_from_other_writers_are_kept_and_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tasks.json");
+        let mut first = JsonStore::open(path.clone()).unwrap();
+        let mut second = JsonStore::open(path.clone()).unwrap();
+        first.load().unwrap();
+        second.load().unwrap();
+
+        let mut task = Task::default();
+        task.set_id(Some(1));
+        task.set_name("first".to_string());
+        assert_eq!(first.insert(&task).unwrap(), 1);
+        task.set_name("second".to_string());
+        // The id is taken by now, so the task moves to the next one
+        assert_eq!(second.insert(&task).unwrap(), 2);
+
+        // The second store wrote on top of the first one's task, which it still has to
+        // hand out, even though the file hasn't changed since its own write
+        assert!(second.has_changed().unwrap());
+        assert_eq!(second.load().unwrap().len(), 2);
+        assert!(!second.has_changed().unwrap());
+
+        assert!(first.has_changed().unwrap());
+        let mut names: Vec<_> = first
+            .load()
+            .unwrap()
+            .into_values()
+            .map(|t| t.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, ["first", "second"]);
+    }
+}
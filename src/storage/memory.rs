@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+
+use super::{insert_new, task_id, TaskStore};
+use crate::{app::Id, error::StorageError, task::Task};
+
+/// Keeps tasks in memory only. Useful for tests and dry runs.
+#[derive(Default)]
+pub struct MemoryStore {
+    tasks: HashMap<Id, Task>,
+}
+
+impl MemoryStore {
+    pub fn new(tasks: HashMap<Id, Task>) -> Self {
+        Self { tasks }
+    }
+}
+
+impl TaskStore for MemoryStore {
+    fn load(&mut self) -> Result<HashMap<Id, Task>, StorageError> {
+        Ok(self.tasks.clone())
+    }
+
+    fn insert(&mut self, task: &Task) -> Result<Id, StorageError> {
+        Ok(insert_new(&mut self.tasks, task))
+    }
+
+    fn update(&mut self, task: &Task) -> Result<(), StorageError> {
+        self.tasks.insert(task_id(task), task.clone());
+        Ok(())
+    }
+
+    fn delete(&mut self, id: Id) -> Result<(), StorageError> {
+        self.tasks.remove(&id);
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<Task>, StorageError> {
+        Ok(self.tasks.values().cloned().collect())
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use crate::{
+    app::Id,
+    configuration::{Paths, StorageBackend},
+    error::StorageError,
+    task::Task,
+};
+
+mod json;
+mod memory;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+pub use json::JsonStore;
+pub use memory::MemoryStore;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
+
+/// Persistence for tasks. `App` keeps its own copy of the tasks and forwards every
+/// mutation to the store, so implementations are free to persist incrementally.
+pub trait TaskStore {
+    /// Reads all the tasks from the underlying storage
+    fn load(&mut self) -> Result<HashMap<Id, Task>, StorageError>;
+
+    /// Stores a new task and returns its id. If another process already took the task's
+    /// id, the task is stored under a new one.
+    fn insert(&mut self, task: &Task) -> Result<Id, StorageError>;
+
+    /// Replaces an existing task
+    fn update(&mut self, task: &Task) -> Result<(), StorageError>;
+
+    fn delete(&mut self, id: Id) -> Result<(), StorageError>;
+
+    fn list(&self) -> Result<Vec<Task>, StorageError>;
+
+    /// Whether someone else changed the tasks since they were last loaded
+    fn has_changed(&self) -> Result<bool, StorageError> {
+        Ok(false)
+    }
+}
+
+pub(crate) fn task_id(task: &Task) -> Id {
+    task.id.expect("Stored tasks should always have an id")
+}
+
+/// Adds `task` to `tasks`, moving it to a free id if its own is taken
+pub(crate) fn insert_new(tasks: &mut HashMap<Id, Task>, task: &Task) -> Id {
+    let mut id = task_id(task);
+    if tasks.contains_key(&id) {
+        id = tasks.keys().max().unwrap_or(&0) + 1;
+    }
+
+    let mut task = task.clone();
+    task.id = Some(id);
+    tasks.insert(id, task);
+    id
+}
+
+/// Opens the store configured in the settings
+pub fn open_store(
+    backend: &StorageBackend,
+    paths: &Paths,
+) -> Result<Box<dyn TaskStore>, StorageError> {
+    match backend {
+        StorageBackend::Json => Ok(Box::new(JsonStore::open(paths.db_file(backend))?)),
+        StorageBackend::Sqlite => open_sqlite_store(paths),
+    }
+}
+
+#[cfg(feature = "sqlite")]
+fn open_sqlite_store(paths: &Paths) -> Result<Box<dyn TaskStore>, StorageError> {
+    let path = paths.db_file(&StorageBackend::Sqlite);
+    crate::utils::ensure_parent_dir(&path)?;
+    let mut store = SqliteStore::open(path)?;
+
+    // Bring over the tasks from the default JSON database, until that succeeds once
+    let json_file = paths.db_file(&StorageBackend::Json);
+    if paths.db_file.is_none() && json_file.exists() && !store.has_imported_json()? {
+        store.import_json(&json_file)?;
+    }
+
+    Ok(Box::new(store))
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn open_sqlite_store(paths: &Paths) -> Result<Box<dyn TaskStore>, StorageError> {
+    Err(StorageError::Database {
+        path: paths.db_file(&StorageBackend::Sqlite),
+        message: "todui was built without the `sqlite` feature".to_string(),
+    })
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    fn paths(dir: &Path) -> Paths {
+        Paths {
+            dir: Some(dir.to_path_buf()),
+            config_file: dir.join("settings.json"),
+            db_file: None,
+        }
+    }
+
+    fn save_json(paths: &Paths, names: &[&str]) {
+        let tasks = names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| {
+                let mut task = Task::default();
+                task.set_id(Some(id));
+                task.set_name(name.to_string());
+                (id, task)
+            })
+            .collect();
+        crate::utils::save_tasks(&paths.db_file(&StorageBackend::Json), &tasks).unwrap();
+    }
+
+    fn names(store: &dyn TaskStore) -> Vec<String> {
+        let mut names: Vec<_> = store.list().unwrap().into_iter().map(|t| t.name).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn json_tasks_are_imported_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = paths(dir.path());
+        save_json(&paths, &["a", "b"]);
+        let store = open_store(&StorageBackend::Sqlite, &paths).unwrap();
+        assert_eq!(names(store.as_ref()), ["a", "b"]);
+        drop(store);
+
+        save_json(&paths, &["a", "b", "c"]);
+        let store = open_store(&StorageBackend::Sqlite, &paths).unwrap();
+        assert_eq!(names(store.as_ref()), ["a", "b"]);
+    }
+
+    #[test]
+    fn failed_import_is_retried() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = paths(dir.path());
+        fs::write(paths.db_file(&StorageBackend::Json), "{\"tasks\": ").unwrap();
+        assert!(open_store(&StorageBackend::Sqlite, &paths).is_err());
+        assert!(paths.db_file(&StorageBackend::Sqlite).exists());
+
+        save_json(&paths, &["a"]);
+        let store = open_store(&StorageBackend::Sqlite, &paths).unwrap();
+        assert_eq!(names(store.as_ref()), ["a"]);
+    }
+
+    #[test]
+    fn databases_from_before_the_marker_are_not_imported_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = paths(dir.path());
+        save_json(&paths, &["a"]);
+        drop(open_store(&StorageBackend::Sqlite, &paths).unwrap());
+
+        let conn = rusqlite::Connection::open(paths.db_file(&StorageBackend::Sqlite)).unwrap();
+        conn.execute_batch("DROP TABLE meta").unwrap();
+        drop(conn);
+
+        save_json(&paths, &["a", "b"]);
+        let store = open_store(&StorageBackend::Sqlite, &paths).unwrap();
+        assert_eq!(names(store.as_ref()), ["a"]);
+    }
+}
@@ -0,0 +1,441 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection};
+use serde_json::Value;
+
+use super::{task_id, TaskStore};
+use crate::{app::Id, error::StorageError, migrations, task::Task, utils};
+
+// Stored in `PRAGMA user_version`
+const SCHEMA_VERSION: u32 = 4;
+
+// `ROW_MIGRATIONS[i]` upgrades the JSON of every row from version `i + 1` to `i + 2`
+const ROW_MIGRATIONS: [fn(&mut Value); SCHEMA_VERSION as usize - 1] = [
+    migrations::add_series_id,
+    migrations::add_has_time,
+    migrations::float_dates,
+];
+
+// The whole task is kept as JSON in `data` so new task fields don't need a table
+// migration. The other columns duplicate what we filter and sort on.
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS tasks (
+        id INTEGER PRIMARY KEY,
+        date TEXT,
+        task_group TEXT,
+        complete INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS tasks_date ON tasks (date);
+    CREATE INDEX IF NOT EXISTS tasks_group ON tasks (task_group);
+    CREATE INDEX IF NOT EXISTS tasks_complete ON tasks (complete);
+    CREATE TABLE IF NOT EXISTS meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+";
+
+// Set in `meta` once the tasks of the JSON database were imported
+const JSON_IMPORTED: &str = "json_imported";
+
+/// Stores tasks in a SQLite database, one row per task. Changes only touch the
+/// affected row.
+pub struct SqliteStore {
+    path: PathBuf,
+    conn: Connection,
+    /// Only changes when another connection commits to the database
+    data_version: i64,
+}
+
+impl SqliteStore {
+    pub fn open(path: PathBuf) -> Result<Self, StorageError> {
+        let mut conn = Connection::open(&path).map_err(database_error(&path))?;
+
+        let found: u32 = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(database_error(&path))?;
+        if found > SCHEMA_VERSION {
+            return Err(StorageError::SchemaVersion {
+                path,
+                found,
+                supported: SCHEMA_VERSION,
+            });
+        }
+
+        let has_meta: bool = conn
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE name = 'meta')",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(database_error(&path))?;
+
+        // Before version 3 every task had a date
+        if (1..3).contains(&found) {
+            allow_null_dates(&mut conn).map_err(database_error(&path))?;
+        }
+        conn.execute_batch(SCHEMA).map_err(database_error(&path))?;
+        // Version 0 is a database that was just created
+        if found > 0 {
+            for migration in &ROW_MIGRATIONS[found as usize - 1..] {
+                upgrade_rows(&mut conn, *migration).map_err(database_error(&path))?;
+            }
+            if found < SCHEMA_VERSION {
+                refresh_columns(&mut conn).map_err(database_error(&path))?;
+            }
+        }
+        // Databases from before the marker got their tasks imported when they were created
+        if found > 0 && !has_meta {
+            conn.execute(
+                "INSERT INTO meta (key, value)
+                 SELECT ?1, '' WHERE EXISTS (SELECT 1 FROM tasks)",
+                params![JSON_IMPORTED],
+            )
+            .map_err(database_error(&path))?;
+        }
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)
+            .map_err(database_error(&path))?;
+        Ok(Self {
+            path,
+            conn,
+            data_version: 0,
+        })
+    }
+
+    /// Copies every task from a `tasks.json` file into the database, returning how many
+    /// tasks were imported. Tasks that already exist are overwritten.
+    ///
+    /// The import is all or nothing, and marks the database as imported when it succeeds.
+    pub fn import_json(&mut self, json_file: &Path) -> Result<usize, StorageError> {
+        // Files from older versions are rewritten when they are read
+        let tasks = {
+            let _lock = utils::lock_file(json_file, true)?;
+            utils::load_tasks(json_file)?
+        };
+
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(database_error(&self.path))?;
+        for task in tasks.values() {
+            upsert(&tx, task).map_err(database_error(&self.path))?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+            params![JSON_IMPORTED, json_file.display().to_string()],
+        )
+        .map_err(database_error(&self.path))?;
+        tx.commit().map_err(database_error(&self.path))?;
+
+        Ok(tasks.len())
+    }
+
+    /// Whether the tasks of a JSON database were imported into this one
+    pub fn has_imported_json(&self) -> Result<bool, StorageError> {
+        self.conn
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM meta WHERE key = ?1)",
+                params![JSON_IMPORTED],
+                |row| row.get(0),
+            )
+            .map_err(database_error(&self.path))
+    }
+
+    fn data_version(&self) -> Result<i64, StorageError> {
+        self.conn
+            .query_row("PRAGMA data_version", [], |row| row.get(0))
+            .map_err(database_error(&self.path))
+    }
+
+    fn parse_row(&self, id: Id, data: &str) -> Result<Task, StorageError> {
+        serde_json::from_str(data).map_err(|e| StorageError::parse(&self.path, &e, Some(id)))
+    }
+}
+
+impl TaskStore for SqliteStore {
+    fn load(&mut self) -> Result<HashMap<Id, Task>, StorageError> {
+        self.data_version = self.data_version()?;
+        let tasks = self.list()?;
+        Ok(tasks.into_iter().map(|t| (task_id(&t), t)).collect())
+    }
+
+    fn insert(&mut self, task: &Task) -> Result<Id, StorageError> {
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(database_error(&self.path))?;
+        let taken: bool = tx
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM tasks WHERE id = ?1)",
+                params![task_id(task)],
+                |row| row.get(0),
+            )
+            .map_err(database_error(&self.path))?;
+
+        let mut task = task.clone();
+        if taken {
+            let id: Id = tx
+                .query_row("SELECT MAX(id) + 1 FROM tasks", [], |row| row.get(0))
+                .map_err(database_error(&self.path))?;
+            task.id = Some(id);
+        }
+        upsert(&tx, &task).map_err(database_error(&self.path))?;
+        tx.commit().map_err(database_error(&self.path))?;
+        Ok(task_id(&task))
+    }
+
+    fn update(&mut self, task: &Task) -> Result<(), StorageError> {
+        upsert(&self.conn, task).map_err(database_error(&self.path))
+    }
+
+    fn delete(&mut self, id: Id) -> Result<(), StorageError> {
+        self.conn
+            .execute("DELETE FROM tasks WHERE id = ?1", params![id])
+            .map_err(database_error(&self.path))?;
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<Task>, StorageError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, data FROM tasks ORDER BY date IS NULL, date")
+            .map_err(database_error(&self.path))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, Id>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(database_error(&self.path))?;
+
+        let mut tasks = vec![];
+        for row in rows {
+            let (id, data) = row.map_err(database_error(&self.path))?;
+            tasks.push(self.parse_row(id, &data)?);
+        }
+        Ok(tasks)
+    }
+
+    fn has_changed(&self) -> Result<bool, StorageError> {
+        Ok(self.data_version()? != self.data_version)
+    }
+}
+
+/// SQLite can't drop the NOT NULL constraint of a column, so the table is copied over
+fn allow_null_dates(conn: &mut Connection) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    tx.execute_batch(
+        "ALTER TABLE tasks RENAME TO old_tasks;
+         DROP INDEX tasks_date;
+         DROP INDEX tasks_group;
+         DROP INDEX tasks_complete;",
+    )?;
+    tx.execute_batch(SCHEMA)?;
+    tx.execute_batch(
+        "INSERT INTO tasks (id, date, task_group, complete, data)
+         SELECT id, date, task_group, complete, data FROM old_tasks;
+         DROP TABLE old_tasks;",
+    )?;
+    tx.commit()
+}
+
+fn upgrade_rows(conn: &mut Connection, migration: fn(&mut Value)) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    let rows = tx
+        .prepare("SELECT id, data FROM tasks")?
+        .query_map([], |row| {
+            Ok((row.get::<_, Id>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    for (id, data) in rows {
+        // Rows that don't parse are left alone, and reported when they are loaded
+        let Ok(mut task) = serde_json::from_str::<Value>(&data) else {
+            continue;
+        };
+        migration(&mut task);
+        tx.execute(
+            "UPDATE tasks SET data = ?1 WHERE id = ?2",
+            params![task.to_string(), id],
+        )?;
+    }
+    tx.commit()
+}
+
+/// Recomputes the columns from the upgraded rows. Tasks due on a day were stored at the
+/// end of that day in the time zone of the time, so their dates wouldn't sort with the
+/// ones stored since
+fn refresh_columns(conn: &mut Connection) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    let rows = tx
+        .prepare("SELECT id, data FROM tasks")?
+        .query_map([], |row| {
+            Ok((row.get::<_, Id>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    for (id, data) in rows {
+        // Rows that don't parse are left alone, and reported when they are loaded
+        let Ok(mut task) = serde_json::from_str::<Task>(&data) else {
+            continue;
+        };
+        task.id = Some(id);
+        upsert(&tx, &task)?;
+    }
+    tx.commit()
+}
+
+fn upsert(conn: &Connection, task: &Task) -> rusqlite::Result<()> {
+    let data = serde_json::to_string(task).expect("Unable to serialize task");
+    // Stored in UTC so that the text column sorts chronologically
+    let date = task
+        .date
+        .map(|date| date.naive_utc().format("%Y-%m-%dT%H:%M:%S").to_string());
+    conn.execute(
+        "INSERT INTO tasks (id, date, task_group, complete, data)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT (id) DO UPDATE SET
+            date = excluded.date,
+            task_group = excluded.task_group,
+            complete = excluded.complete,
+            data = excluded.data",
+        params![task_id(task), date, task.group, task.complete, data],
+    )?;
+    Ok(())
+}
+
+fn database_error(path: &Path) -> impl FnOnce(rusqlite::Error) -> StorageError + '_ {
+    move |e| StorageError::Database {
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_database(path: &Path, version: u32, rows: &[(Id, &str, Value)]) {
+        let conn = Connection::open(path).unwrap();
+        conn.execute_batch(SCHEMA).unwrap();
+        for (id, date, data) in rows {
+            conn.execute(
+                "INSERT INTO tasks (id, date, task_group, complete, data)
+                 VALUES (?1, ?2, NULL, 0, ?3)",
+                params![id, date, data.to_string()],
+            )
+            .unwrap();
+        }
+        conn.pragma_update(None, "user_version", version).unwrap();
+    }
+
+    fn row(id: Id, name: &str, date: &str, has_time: bool) -> Value {
+        serde_json::json!({
+            "id": id, "series_id": uuid::Uuid::new_v4(), "name": name, "date": date,
+            "has_time": has_time, "repeats": "Never", "group": null, "description": null,
+            "url": null, "complete": false
+        })
+    }
+
+    #[test]
+    fn upgraded_dates_still_sort() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tasks.db");
+        // Written by a version 3 store, which kept the tasks due on a day at the end of
+        // that day in the zone of the time. The 10th was written at UTC+14 and the 9th at
+        // UTC-11, so their old dates sort the other way around
+        create_database(
+            &path,
+            3,
+            &[
+                (
+                    1,
+                    "2026-03-10T09:59:59",
+                    row(1, "tenth", "2026-03-10T23:59:59+14:00", false),
+                ),
+                (
+                    2,
+                    "2026-03-10T10:59:59",
+                    row(2, "ninth", "2026-03-09T23:59:59-11:00", false),
+                ),
+                (
+                    3,
+                    "2026-03-13T00:00:00",
+                    row(3, "thirteenth", "2026-03-13T00:00:00+00:00", true),
+                ),
+            ],
+        );
+
+        let mut store = SqliteStore::open(path).unwrap();
+        let tasks = store.list().unwrap();
+        let names: Vec<_> = tasks.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["ninth", "tenth", "thirteenth"]);
+        let days: Vec<_> = tasks.iter().map(|t| t.date.unwrap().date_naive()).collect();
+        assert_eq!(days[..2], [date(2026, 3, 9), date(2026, 3, 10)]);
+        assert!(!tasks[0].has_time);
+
+        // Rows written since sort with the upgraded ones
+        let mut task = tasks[0].clone();
+        task.set_id(Some(4));
+        task.set_name("eleventh".to_string());
+        task.date = Some(utils::end_of_day(date(2026, 3, 11)));
+        store.insert(&task).unwrap();
+        let names: Vec<_> = store.list().unwrap().into_iter().map(|t| t.name).collect();
+        assert_eq!(names, ["ninth", "tenth", "eleventh", "thirteenth"]);
+    }
+
+    #[test]
+    fn changes_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tasks.db");
+        let mut store = SqliteStore::open(path.clone()).unwrap();
+        assert!(store.load().unwrap().is_empty());
+
+        let mut task = Task::default();
+        task.set_id(Some(1));
+        task.set_name("a".to_string());
+        task.set_date(utils::end_of_day(date(2026, 3, 10)));
+        assert_eq!(store.insert(&task).unwrap(), 1);
+        // The id is taken, so the task moves to the next one
+        task.set_name("b".to_string());
+        assert_eq!(store.insert(&task).unwrap(), 2);
+
+        task.set_id(Some(2));
+        task.set_group("Work".to_string());
+        task.complete = true;
+        store.update(&task).unwrap();
+        store.delete(1).unwrap();
+
+        let tasks = SqliteStore::open(path).unwrap().load().unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[&2], task);
+    }
+
+    #[test]
+    fn only_other_connections_trigger_a_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tasks.db");
+        let mut first = SqliteStore::open(path.clone()).unwrap();
+        let mut second = SqliteStore::open(path).unwrap();
+        first.load().unwrap();
+        second.load().unwrap();
+        assert!(!first.has_changed().unwrap());
+
+        let mut task = Task::default();
+        task.set_id(Some(1));
+        first.insert(&task).unwrap();
+        // Its own writes are in memory already
+        assert!(!first.has_changed().unwrap());
+        assert!(second.has_changed().unwrap());
+
+        assert_eq!(second.load().unwrap().len(), 1);
+        assert!(!second.has_changed().unwrap());
+        second.delete(1).unwrap();
+        assert!(first.has_changed().unwrap());
+        assert!(first.load().unwrap().is_empty());
+        assert!(!first.has_changed().unwrap());
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> chrono::NaiveDate {
+        chrono::NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+}
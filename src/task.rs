@@ -1,13 +1,19 @@
-use crate::{day_of_week::DayOfWeek, repeat::Repeat};
+use crate::repeat::{self, Anchor, Repeat, RepeatFrom};
+use crate::utils;
 use anyhow::Result;
-use chrono::{DateTime, Datelike, Days, Duration, Local, Months, TimeZone};
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Datelike, Local, NaiveDate, ParseError, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{ser::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::cmp::Ordering;
+use uuid::Uuid;
 
+/// Dates are stored in UTC, so they point at the same instant wherever they're read
 pub fn serialize_dt<S>(date: &DateTime<Local>, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
 {
-    let s = date.format("%+").to_string();
+    let s = date.with_timezone(&Utc).format("%+").to_string();
     serializer.serialize_str(&s)
 }
 
@@ -16,21 +22,189 @@ where
     D: serde::Deserializer<'de>,
 {
     let s = String::deserialize(deserializer)?;
-    let dt = Local.datetime_from_str(&s, "%+").unwrap();
-    Ok(dt)
+    parse_dt(&s).map_err(|e| serde::de::Error::custom(format!("invalid date '{}': {}", s, e)))
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+pub fn serialize_opt_dt<S>(date: &Option<DateTime<Local>>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match date {
+        Some(date) => serialize_dt(date, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+pub fn deserialize_opt_dt<'de, D>(deserializer: D) -> Result<Option<DateTime<Local>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let s = Option::<String>::deserialize(deserializer)?;
+    s.map(|s| {
+        parse_dt(&s).map_err(|e| serde::de::Error::custom(format!("invalid date '{}': {}", s, e)))
+    })
+    .transpose()
+}
+
+/// Like `deserialize_opt_dt`, but a day without a time is due at its end in the local
+/// time zone, whichever it is. See `Task::serialize`.
+pub fn deserialize_due<'de, D>(deserializer: D) -> Result<Option<DateTime<Local>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let s = Option::<String>::deserialize(deserializer)?;
+    s.map(|s| match NaiveDate::parse_from_str(&s, "%Y-%m-%d") {
+        Ok(day) => Ok(utils::end_of_day(day)),
+        Err(_) => parse_dt(&s)
+            .map_err(|e| serde::de::Error::custom(format!("invalid date '{}': {}", s, e))),
+    })
+    .transpose()
+}
+
+fn parse_dt(s: &str) -> Result<DateTime<Local>, ParseError> {
+    DateTime::parse_from_str(s, "%+").map(|date| date.with_timezone(&Local))
+}
+
+pub fn serialize_opt_tz<S>(tz: &Option<Tz>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match tz {
+        Some(tz) => serializer.serialize_str(tz.name()),
+        None => serializer.serialize_none(),
+    }
+}
+
+pub fn deserialize_opt_tz<'de, D>(deserializer: D) -> Result<Option<Tz>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let s = Option::<String>::deserialize(deserializer)?;
+    s.map(|s| {
+        s.parse()
+            .map_err(|_| serde::de::Error::custom(format!("unknown time zone '{}'", s)))
+    })
+    .transpose()
+}
+
+pub fn serialize_opt_day<S>(day: &Option<NaiveDate>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match day {
+        Some(day) => serializer.serialize_str(&day.format("%Y-%m-%d").to_string()),
+        None => serializer.serialize_none(),
+    }
+}
+
+pub fn deserialize_opt_day<'de, D>(deserializer: D) -> Result<Option<NaiveDate>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let s = Option::<String>::deserialize(deserializer)?;
+    s.map(|s| {
+        NaiveDate::parse_from_str(&s, "%Y-%m-%d")
+            .map_err(|e| serde::de::Error::custom(format!("invalid day '{}': {}", s, e)))
+    })
+    .transpose()
+}
+
+/// One completion of a repeating task
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Completion {
+    #[serde(serialize_with = "serialize_dt", deserialize_with = "deserialize_dt")]
+    pub completed_at: DateTime<Local>,
+    /// When the completed occurrence was due
+    #[serde(serialize_with = "serialize_dt", deserialize_with = "deserialize_dt")]
+    pub due: DateTime<Local>,
+    pub on_time: bool,
+}
+
+impl Completion {
+    pub fn new(due: DateTime<Local>, completed_at: DateTime<Local>) -> Self {
+        Self {
+            completed_at,
+            due,
+            on_time: completed_at <= due,
+        }
+    }
+}
+
+/// Sums up the completions dropped from the front of a task's history, so its streaks
+/// still count them
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct EarlierCompletions {
+    /// The longest run of on-time completions among them
+    pub best_streak: usize,
+    /// How many of them in a row were on time, counting back from the last one
+    pub streak: usize,
+}
+
+// Serialized by hand below, around the derived impls
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(remote = "Self")]
 pub struct Task {
     pub id: Option<usize>,
+    /// Identifies the task across processes and machines. Unlike `id`, it is never
+    /// reassigned
+    // Tasks saved in the history or the trash before series ids existed get a new one
+    #[serde(default = "Uuid::new_v4")]
+    pub series_id: Uuid,
     pub name: String,
-    #[serde(serialize_with = "serialize_dt", deserialize_with = "deserialize_dt")]
-    pub date: DateTime<Local>,
+    /// When the task is due, if ever
+    #[serde(
+        serialize_with = "serialize_opt_dt",
+        deserialize_with = "deserialize_due"
+    )]
+    pub date: Option<DateTime<Local>>,
+    /// Whether the task is due at a time of day. Otherwise it's due on a day, at its end
+    // Tasks saved in the history or the trash before this existed are taken as date-only
+    #[serde(default)]
+    pub has_time: bool,
+    /// The time zone the due time was given in. Repeating tasks stay at that time of day
+    /// there, whichever zone they're viewed from
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_opt_tz",
+        deserialize_with = "deserialize_opt_tz"
+    )]
+    pub timezone: Option<Tz>,
+    /// The day to start working on the task. It's hidden until then
+    #[serde(
+        default,
+        serialize_with = "serialize_opt_day",
+        deserialize_with = "deserialize_opt_day"
+    )]
+    pub start_date: Option<NaiveDate>,
     pub repeats: Repeat,
+    #[serde(default)]
+    pub repeats_from: RepeatFrom,
+    /// The task doesn't repeat after this date
+    #[serde(
+        default,
+        serialize_with = "serialize_opt_dt",
+        deserialize_with = "deserialize_opt_dt"
+    )]
+    pub repeats_until: Option<DateTime<Local>>,
+    /// How many more times the task is due, counting the current occurrence
+    #[serde(default)]
+    pub occurrences_left: Option<u32>,
+    /// Where the occurrences are meant to fall, if a short month or a DST change moved
+    /// the due date
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor: Option<Anchor>,
     pub group: Option<String>,
     pub description: Option<String>,
     pub url: Option<String>,
     pub complete: bool,
+    /// Past completions of a repeating task, oldest first. Carried over to each new
+    /// occurrence
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub completions: Vec<Completion>,
+    /// The completions that `trim_completions` dropped from `completions`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub earlier_completions: Option<EarlierCompletions>,
 }
 
 impl Task {
@@ -43,13 +217,49 @@ impl Task {
     }
 
     pub fn set_date(&mut self, date: DateTime<Local>) {
-        self.date = date;
+        self.date = Some(date);
+    }
+
+    pub fn set_has_time(&mut self, has_time: bool) {
+        self.has_time = has_time;
+    }
+
+    pub fn set_timezone(&mut self, timezone: Tz) {
+        self.timezone = Some(timezone);
+    }
+
+    /// The time zone the task's occurrences follow. Tasks due on a day rather than at a
+    /// time of day follow the local one
+    pub fn zone(&self) -> Option<Tz> {
+        self.timezone.filter(|_| self.has_time)
+    }
+
+    pub fn set_start_date(&mut self, start_date: NaiveDate) {
+        self.start_date = Some(start_date);
+    }
+
+    /// Whether the task has a start date that hasn't come yet
+    pub fn is_scheduled(&self) -> bool {
+        self.start_date
+            .is_some_and(|start| start > Local::now().date_naive())
     }
 
     pub fn set_repeats(&mut self, repeats: Repeat) {
         self.repeats = repeats;
     }
 
+    pub fn set_repeats_from(&mut self, repeats_from: RepeatFrom) {
+        self.repeats_from = repeats_from;
+    }
+
+    pub fn set_repeats_until(&mut self, repeats_until: DateTime<Local>) {
+        self.repeats_until = Some(repeats_until);
+    }
+
+    pub fn set_occurrences_left(&mut self, occurrences_left: u32) {
+        self.occurrences_left = Some(occurrences_left);
+    }
+
     pub fn set_group(&mut self, group: String) {
         self.group = Some(group);
     }
@@ -62,48 +272,218 @@ impl Task {
         self.url = Some(url);
     }
 
-    pub fn set_complete(&mut self) -> Option<Task> {
-        self.complete = true;
-        let date = match &self.repeats {
-            Repeat::DaysOfWeek(days) => {
-                let mut new_date = None;
-                for i in 1..=7 {
-                    let day = self.date + Duration::days(i);
-                    let weekday = DayOfWeek::from_chrono(day.weekday());
-                    if days.contains(&weekday) {
-                        new_date = Some(day);
-                        break;
-                    }
+    /// Marks the task as complete. Repeating tasks return their next occurrence instead,
+    /// or the first one that isn't overdue with `catch_up`.
+    pub fn set_complete(&mut self, catch_up: bool) -> Option<Task> {
+        let was_complete = std::mem::replace(&mut self.complete, true);
+        let date = self.date?;
+        let now = Local::now();
+        let from = match self.repeats_from {
+            RepeatFrom::DueDate => date,
+            RepeatFrom::CompletionDate => repeat::at_same_time(&date, now.date_naive()),
+        };
+
+        let mut occurrences = self.occurrences_after(from);
+        let mut next = occurrences.next();
+        if catch_up {
+            // All of them may be overdue if the series ends, so stop at the last one
+            while next
+                .as_ref()
+                .is_some_and(|task| task.date.is_some_and(|date| date < now))
+            {
+                match occurrences.next() {
+                    Some(occurrence) => next = Some(occurrence),
+                    None => break,
                 }
-                new_date
             }
-            Repeat::Never => None,
-            Repeat::Daily => Some(self.date + Days::new(1)),
-            Repeat::Weekly => Some(self.date + Days::new(7)),
-            Repeat::Monthly => Some(self.date + Months::new(1)),
-            Repeat::Yearly => Some(self.date + Months::new(12)),
+        }
+
+        let completion = Completion::new(date, now);
+        match next {
+            Some(mut new_task) => {
+                new_task.set_incomplete();
+                new_task.completions.push(completion);
+                Some(new_task)
+            }
+            None => {
+                // The series is over, so the last completion stays on the task itself
+                if self.repeats != Repeat::Never && !was_complete {
+                    self.completions.push(completion);
+                }
+                None
+            }
+        }
+    }
+
+    /// Moves a repeating task on to its next occurrence without completing the current
+    /// one. Returns `None` if there is no next occurrence.
+    pub fn skip(&self) -> Option<Task> {
+        self.next_occurrence(self.date.as_ref()?)
+    }
+
+    /// How many of the occurrences after the current one are overdue already
+    pub fn missed_occurrences(&self) -> usize {
+        let Some(date) = self.date else {
+            return 0;
+        };
+        if self.repeats_from == RepeatFrom::CompletionDate {
+            return 0;
+        }
+        let now = Local::now();
+        self.occurrences_after(date)
+            .take_while(|task| task.date.is_some_and(|date| date < now))
+            .count()
+    }
+
+    /// The current due date followed by the ones after it, until the series ends
+    pub fn due_dates(&self) -> impl Iterator<Item = DateTime<Local>> + '_ {
+        let next: Box<dyn Iterator<Item = DateTime<Local>>> = match (self.date, self.zone()) {
+            (None, _) => Box::new(std::iter::empty()),
+            (Some(date), Some(tz)) => {
+                let date = date.with_timezone(&tz);
+                let anchor = self.anchor.unwrap_or_else(|| Anchor::of(&date));
+                let next = self.repeats.occurrences(&date, anchor);
+                Box::new(next.map(|d| d.with_timezone(&Local)))
+            }
+            (Some(date), None) => {
+                let anchor = self.anchor.unwrap_or_else(|| Anchor::of(&date));
+                Box::new(self.repeats.occurrences(&date, anchor))
+            }
         };
+        // The current occurrence counts towards the ones left
+        let left = self.occurrences_left.map_or(usize::MAX, |left| {
+            usize::try_from(left).unwrap_or(usize::MAX)
+        });
+        let next = next
+            .take(left.saturating_sub(1))
+            .take_while(|date| self.repeats_until.is_none_or(|until| *date <= until));
+        self.date.into_iter().chain(next)
+    }
 
-        if let Some(date) = date {
-            let mut new_task = self.clone();
-            new_task.set_date(date);
-            new_task.set_incomplete();
-            Some(new_task)
-        } else {
-            None
+    /// Orders tasks by due date, with the ones that have none last
+    pub fn cmp_by_date(&self, other: &Task) -> Ordering {
+        self.date
+            .is_none()
+            .cmp(&other.date.is_none())
+            .then(self.date.cmp(&other.date))
+    }
+
+    /// The task at each of its occurrences after `date`
+    fn occurrences_after(&self, date: DateTime<Local>) -> impl Iterator<Item = Task> {
+        let first = self.next_occurrence(&date);
+        std::iter::successors(first, |task| task.next_occurrence(task.date.as_ref()?))
+    }
+
+    /// The task at its first occurrence after `date`, or `None` if the series ends
+    /// before that
+    fn next_occurrence(&self, date: &DateTime<Local>) -> Option<Task> {
+        if self.occurrences_left.is_some_and(|left| left <= 1) {
+            return None;
+        }
+        let (next_date, anchor) = match self.zone() {
+            Some(tz) => self.next_date_after(&date.with_timezone(&tz))?,
+            None => self.next_date_after(date)?,
+        };
+        if self.repeats_until.is_some_and(|until| next_date > until) {
+            return None;
+        }
+
+        let mut task = self.clone();
+        task.set_date(next_date);
+        // The next occurrence starts as long before it's due as this one
+        if let Some(current) = self.date {
+            task.start_date = self
+                .start_date
+                .map(|start| start + (next_date.date_naive() - current.date_naive()));
         }
+        task.anchor = anchor;
+        task.set_repeats(self.repeats.after_occurrence());
+        task.occurrences_left = self.occurrences_left.map(|left| left - 1);
+        Some(task)
+    }
+
+    /// The first due date after `date`, at the anchor's time of day in the zone of
+    /// `date`. Also returns the anchor, if the due date strays from it.
+    fn next_date_after<Z: TimeZone>(
+        &self,
+        date: &DateTime<Z>,
+    ) -> Option<(DateTime<Local>, Option<Anchor>)> {
+        // Counting from another date, like the completion date, starts over from there
+        let anchor = match self.anchor {
+            Some(anchor) if self.date.is_some_and(|current| current == *date) => anchor,
+            _ => Anchor::of(date),
+        };
+        let next_date = self.repeats.next_date_from(date, &anchor)?;
+        let moved = next_date.time() != anchor.time
+            || self.repeats.keeps_day_of_month() && next_date.day() != anchor.day;
+        Some((next_date.with_timezone(&Local), moved.then_some(anchor)))
     }
 
     pub fn set_incomplete(&mut self) -> Option<Task> {
+        // Completing the last occurrence of a series recorded it on the task
+        let last_due = self.completions.last().map(|c| c.due);
+        if self.complete && self.date.is_some() && last_due == self.date {
+            self.completions.pop();
+        }
         self.complete = false;
         None
     }
 
-    pub fn toggle_complete(&mut self) -> Option<Task> {
+    /// How many times in a row the task was completed on time, counting back from the
+    /// last completion. Letting the current occurrence go overdue breaks the streak.
+    pub fn streak(&self) -> usize {
+        if !self.complete && self.date.is_some_and(|date| date < Local::now()) {
+            return 0;
+        }
+        let streak = self
+            .completions
+            .iter()
+            .rev()
+            .take_while(|c| c.on_time)
+            .count();
+        match &self.earlier_completions {
+            Some(earlier) if streak == self.completions.len() => streak + earlier.streak,
+            _ => streak,
+        }
+    }
+
+    /// The longest run of on-time completions
+    pub fn best_streak(&self) -> usize {
+        let earlier = self.earlier_completions.clone().unwrap_or_default();
+        let mut best = earlier.best_streak;
+        let mut current = earlier.streak;
+        for completion in &self.completions {
+            current = if completion.on_time { current + 1 } else { 0 };
+            best = best.max(current);
+        }
+        best
+    }
+
+    /// Keeps the last `keep` completions, and at least one so that `set_incomplete` can
+    /// forget it. The older ones only count towards the streaks
+    pub fn trim_completions(&mut self, keep: usize) {
+        let excess = self.completions.len().saturating_sub(keep.max(1));
+        if excess == 0 {
+            return;
+        }
+        let earlier = self
+            .earlier_completions
+            .get_or_insert_with(Default::default);
+        for completion in self.completions.drain(..excess) {
+            earlier.streak = if completion.on_time {
+                earlier.streak + 1
+            } else {
+                0
+            };
+            earlier.best_streak = earlier.best_streak.max(earlier.streak);
+        }
+    }
+
+    pub fn toggle_complete(&mut self, catch_up: bool) -> Option<Task> {
         if self.complete {
             self.set_incomplete()
         } else {
-            self.set_complete()
+            self.set_complete(catch_up)
         }
     }
 }
@@ -112,13 +492,340 @@ impl Default for Task {
     fn default() -> Self {
         Self {
             id: None,
+            series_id: Uuid::new_v4(),
             name: "".to_string(),
-            date: Local::now(),
+            date: None,
+            has_time: false,
+            timezone: None,
+            start_date: None,
             repeats: Repeat::Never,
+            repeats_from: RepeatFrom::DueDate,
+            repeats_until: None,
+            occurrences_left: None,
+            anchor: None,
             group: None,
             description: None,
             url: None,
             complete: false,
+            completions: vec![],
+            earlier_completions: None,
+        }
+    }
+}
+
+/// Tasks due on a day are stored as just the day, so they stay on it when the local time
+/// zone changes. The others are stored in UTC.
+impl Serialize for Task {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut value =
+            Task::serialize(self, serde_json::value::Serializer).map_err(S::Error::custom)?;
+        if let (Some(date), false) = (self.date, self.has_time) {
+            value["date"] = Value::String(date.format("%Y-%m-%d").to_string());
+        }
+        value.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Task {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Task::deserialize(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day_of_week::DayOfWeek;
+    use chrono::NaiveTime;
+    use proptest::prelude::*;
+
+    fn daily(date: DateTime<Local>) -> Task {
+        let mut task = Task::default();
+        task.set_date(date);
+        task.set_has_time(true);
+        task.set_repeats(Repeat::Daily);
+        task
+    }
+
+    #[test]
+    fn completing_the_last_occurrence_records_it() {
+        let due = Local::now() + chrono::Duration::days(1);
+        let mut task = daily(due);
+        task.set_occurrences_left(1);
+
+        assert!(task.set_complete(false).is_none());
+        assert!(task.complete);
+        assert_eq!(task.completions.len(), 1);
+        assert_eq!(task.completions[0].due, due);
+        assert!(task.completions[0].on_time);
+        assert_eq!(task.streak(), 1);
+
+        // Completing it again doesn't count twice
+        assert!(task.set_complete(false).is_none());
+        assert_eq!(task.completions.len(), 1);
+    }
+
+    #[test]
+    fn uncompleting_the_last_occurrence_forgets_it() {
+        let mut task = daily(Local::now() + chrono::Duration::days(1));
+        task.set_occurrences_left(2);
+        let mut last = task.set_complete(false).unwrap();
+        last.set_complete(false);
+        assert_eq!(last.completions.len(), 2);
+
+        last.set_incomplete();
+        assert!(!last.complete);
+        assert_eq!(last.completions.len(), 1);
+        assert_eq!(last.completions[0].due, task.date.unwrap());
+    }
+
+    #[test]
+    fn completing_a_one_off_task_records_nothing() {
+        let mut task = Task::default();
+        task.set_date(Local::now());
+        assert!(task.set_complete(false).is_none());
+        assert!(task.completions.is_empty());
+    }
+
+    #[test]
+    fn trimmed_completions_still_count_towards_the_streaks() {
+        let due = Local::now() - chrono::Duration::days(30);
+        let on_time = |on_time| Completion {
+            completed_at: due,
+            due,
+            on_time,
+        };
+        let mut task = daily(Local::now() + chrono::Duration::days(1));
+        // A best streak of 3, then a streak of 4 that goes on past the trimmed ones
+        task.completions = [true, true, true, false, true, true, true, true]
+            .map(on_time)
+            .to_vec();
+        let expected = (task.streak(), task.best_streak());
+        assert_eq!(expected, (4, 4));
+
+        for keep in (0..=8).rev() {
+            task.trim_completions(keep);
+            assert_eq!(task.completions.len(), keep.max(1));
+            assert_eq!((task.streak(), task.best_streak()), expected, "{keep}");
+        }
+
+        task.completions.push(on_time(true));
+        task.trim_completions(1);
+        assert_eq!((task.streak(), task.best_streak()), (5, 5));
+        task.completions.push(on_time(false));
+        task.trim_completions(1);
+        assert_eq!((task.streak(), task.best_streak()), (0, 5));
+    }
+
+    // How many occurrences each property follows a series for
+    const STEPS: usize = 30;
+
+    /// The task after each of `task`'s completions, until the series ends or `STEPS`
+    fn completions(task: &Task) -> Vec<Task> {
+        std::iter::successors(Some(task.clone()), |task| task.clone().set_complete(false))
+            .skip(1)
+            .take(STEPS)
+            .collect()
+    }
+
+    fn repeating(date: DateTime<Local>, repeats: Repeat) -> Task {
+        let mut task = Task::default();
+        task.set_date(date);
+        task.set_has_time(true);
+        task.set_repeats(repeats);
+        task
+    }
+
+    /// `None` if the local time zone skips that time
+    fn local(date: NaiveDate, time: NaiveTime) -> Option<DateTime<Local>> {
+        Local.from_local_datetime(&date.and_time(time)).earliest()
+    }
+
+    fn weekday() -> impl Strategy<Value = DayOfWeek> {
+        (1..=7u32).prop_map(DayOfWeek::from_int)
+    }
+
+    fn weekdays() -> impl Strategy<Value = Vec<DayOfWeek>> {
+        proptest::collection::btree_set(1..=7u32, 1..=7)
+            .prop_map(|days| days.into_iter().map(DayOfWeek::from_int).collect())
+    }
+
+    fn repeat() -> impl Strategy<Value = Repeat> {
+        prop_oneof![
+            Just(Repeat::Daily),
+            Just(Repeat::Weekly),
+            Just(Repeat::Monthly),
+            Just(Repeat::Yearly),
+            weekdays().prop_map(Repeat::DaysOfWeek),
+            (1..=60u32).prop_map(Repeat::EveryDays),
+            (1..=4u32, prop_oneof![Just(vec![]), weekdays()])
+                .prop_map(|(interval, days)| Repeat::EveryWeeks { interval, days }),
+            (prop_oneof![1..=5i8, -5..=-1i8], weekday())
+                .prop_map(|(nth, day)| Repeat::NthWeekdayOfMonth { nth, day }),
+            (1..=31u32).prop_map(Repeat::DayOfMonth),
+            Just(Repeat::FirstWeekdayOfQuarter),
+        ]
+    }
+
+    fn day() -> impl Strategy<Value = NaiveDate> {
+        (2000..2040i32, 1..=12u32, 1..=31u32)
+            .prop_filter_map("no such day", |(y, m, d)| NaiveDate::from_ymd_opt(y, m, d))
+    }
+
+    fn time() -> impl Strategy<Value = NaiveTime> {
+        (0..24u32, 0..60u32).prop_map(|(h, m)| NaiveTime::from_hms_opt(h, m, 0).unwrap())
+    }
+
+    #[test]
+    fn month_end_returns_to_the_31st() {
+        let time = NaiveTime::from_hms_opt(9, 0, 0).unwrap();
+        let start = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        let task = repeating(local(start, time).unwrap(), Repeat::Monthly);
+
+        let days: Vec<(u32, u32)> = completions(&task)
+            .iter()
+            .take(3)
+            .map(|t| (t.date.unwrap().month(), t.date.unwrap().day()))
+            .collect();
+        assert_eq!(days, [(2, 29), (3, 31), (4, 30)]);
+    }
+
+    proptest! {
+        #[test]
+        fn next_dates_are_strictly_increasing(
+            start in day(),
+            time in time(),
+            repeats in repeat(),
+        ) {
+            let Some(date) = local(start, time) else {
+                return Ok(());
+            };
+            let task = repeating(date, repeats);
+            let mut previous = task.date.unwrap();
+            for next in completions(&task) {
+                let date = next.date.unwrap();
+                prop_assert!(date > previous, "{} is not after {}", date, previous);
+                previous = date;
+            }
+        }
+
+        #[test]
+        fn monthly_repeats_keep_the_anchor_day(
+            (year, month, day) in (2000..2040i32, 1..=12u32, 28..=31u32),
+            time in time(),
+            yearly in any::<bool>(),
+        ) {
+            let Some(start) = NaiveDate::from_ymd_opt(year, month, day) else {
+                return Ok(());
+            };
+            let Some(date) = local(start, time) else {
+                return Ok(());
+            };
+            let repeats = if yearly { Repeat::Yearly } else { Repeat::Monthly };
+            let task = repeating(date, repeats);
+            for next in completions(&task) {
+                let date = next.date.unwrap().date_naive();
+                let expected = day.min(repeat::days_in_month(date));
+                prop_assert_eq!(date.day(), expected, "{} after {}", date, start);
+            }
+        }
+
+        #[test]
+        fn repeats_keep_the_wall_clock_time_across_dst(
+            tz in prop_oneof![
+                Just(chrono_tz::America::New_York),
+                Just(chrono_tz::Europe::Berlin),
+                Just(chrono_tz::Australia::Sydney),
+                Just(chrono_tz::Australia::Lord_Howe),
+            ],
+            start in day(),
+            time in time(),
+            repeats in prop_oneof![
+                Just(Repeat::Daily),
+                Just(Repeat::Weekly),
+                Just(Repeat::Monthly),
+            ],
+        ) {
+            let Some(date) = tz.from_local_datetime(&start.and_time(time)).earliest() else {
+                return Ok(());
+            };
+            let mut task = repeating(date.with_timezone(&Local), repeats);
+            task.set_timezone(tz);
+
+            for next in completions(&task) {
+                let due = next.date.unwrap().with_timezone(&tz);
+                let wanted = due.date_naive().and_time(time);
+                if tz.from_local_datetime(&wanted).earliest().is_some() {
+                    prop_assert_eq!(due.time(), time, "{} from {}", due, date);
+                } else {
+                    // The time was skipped, so it's due just after the change
+                    prop_assert!(due.naive_local() > wanted, "{} from {}", due, date);
+                    prop_assert!(due.naive_local() - wanted <= chrono::Duration::hours(1));
+                }
+            }
+        }
+
+        #[test]
+        fn occurrences_left_is_never_exceeded(
+            start in day(),
+            time in time(),
+            repeats in repeat(),
+            left in 1..=10u32,
+        ) {
+            let Some(date) = local(start, time) else {
+                return Ok(());
+            };
+            let mut task = repeating(date, repeats);
+            task.set_occurrences_left(left);
+            let nexts = completions(&task);
+            prop_assert!(nexts.len() < left as usize);
+            for (i, next) in nexts.iter().enumerate() {
+                prop_assert_eq!(next.occurrences_left, Some(left - 1 - i as u32));
+            }
+        }
+
+        #[test]
+        fn repeats_until_is_never_exceeded(
+            start in day(),
+            time in time(),
+            repeats in repeat(),
+            days in 0..400i64,
+        ) {
+            let Some(date) = local(start, time) else {
+                return Ok(());
+            };
+            let until = date + chrono::Duration::days(days);
+            let mut task = repeating(date, repeats);
+            task.set_repeats_until(until);
+            for next in completions(&task) {
+                prop_assert!(next.date.unwrap() <= until);
+            }
+        }
+
+        #[test]
+        fn due_dates_match_completing_the_task(
+            start in day(),
+            time in time(),
+            repeats in repeat(),
+            left in proptest::option::of(1..=10u32),
+            days in proptest::option::of(0..400i64),
+        ) {
+            let Some(date) = local(start, time) else {
+                return Ok(());
+            };
+            let mut task = repeating(date, repeats);
+            if let Some(left) = left {
+                task.set_occurrences_left(left);
+            }
+            if let Some(days) = days {
+                task.set_repeats_until(date + chrono::Duration::days(days));
+            }
+            let due_dates: Vec<_> = task.due_dates().take(STEPS + 1).collect();
+            let completed: Vec<_> = std::iter::once(Some(date))
+                .chain(completions(&task).iter().map(|t| t.date))
+                .collect::<Option<_>>()
+                .unwrap();
+            prop_assert_eq!(due_dates, completed);
         }
     }
 }
@@ -1,17 +1,28 @@
-use anyhow::Context;
 use anyhow::Result;
+use chrono::{Local, TimeZone};
+use chrono_tz::Tz;
 
 use crate::configuration::Settings;
-use crate::repeat::Repeat;
+use crate::repeat::{Repeat, RepeatFrom};
 use crate::task::Task;
 use crate::utils;
+use uuid::Uuid;
+
+/// How many due dates to show when previewing a repeating task
+pub const PREVIEW_LEN: usize = 5;
 
 #[derive(Default)]
 pub struct TaskForm {
     pub id: Option<usize>,
+    pub series_id: Option<Uuid>,
     pub name: String,
     pub date: String,
+    pub timezone: String,
+    pub start_date: String,
     pub repeats: String,
+    pub repeats_from: String,
+    pub repeats_until: String,
+    pub occurrences_left: String,
     pub group: String,
     pub description: String,
     pub url: String,
@@ -21,9 +32,38 @@ impl TaskForm {
     pub fn from_task(task: &Task, settings: &Settings) -> Self {
         Self {
             id: task.id,
+            series_id: Some(task.series_id),
             name: task.name.to_string(),
-            date: utils::date_to_input_str(&task.date, settings),
+            // Shown in the task's time zone, which is what it was given in
+            date: task
+                .date
+                .map(|date| match task.zone() {
+                    Some(tz) => utils::date_to_input_str(&date.with_timezone(&tz), true, settings),
+                    None => utils::date_to_input_str(&date, task.has_time, settings),
+                })
+                .unwrap_or_default(),
+            timezone: task
+                .timezone
+                .map(|tz| tz.name().to_string())
+                .unwrap_or_default(),
+            start_date: task
+                .start_date
+                .map(|start| {
+                    start
+                        .format(&settings.date_formats.input_date_format)
+                        .to_string()
+                })
+                .unwrap_or_default(),
             repeats: task.repeats.to_string(),
+            repeats_from: task.repeats_from.to_string(),
+            repeats_until: task
+                .repeats_until
+                .map(|until| utils::date_to_input_str(&until, false, settings))
+                .unwrap_or_default(),
+            occurrences_left: task
+                .occurrences_left
+                .map(|left| left.to_string())
+                .unwrap_or_default(),
             group: task.group.clone().unwrap_or_default(),
             description: task.description.clone().unwrap_or_default(),
             url: task.url.clone().unwrap_or_default(),
@@ -31,19 +71,107 @@ impl TaskForm {
     }
 
     pub fn submit(&mut self, settings: &Settings) -> Result<Task> {
-        let mut task = Task::default();
-
-        let repeat = Repeat::parse_from_str(&self.repeats).context("Invalid repeat format")?;
-        let date = utils::parse_date(&self.date, settings).unwrap_or(utils::get_today());
-
+        let task = self.parse(settings)?;
         if self.name.is_empty() {
             return Err(anyhow::anyhow!("Task name cannot be empty"));
         }
+        Ok(task)
+    }
+
+    /// The task the form describes, even if it can't be submitted yet, like to preview
+    /// its due dates
+    pub fn parse(&self, settings: &Settings) -> Result<Task> {
+        let mut task = Task::default();
+
+        let repeat = Repeat::parse_from_str(&self.repeats, settings.locale)
+            .map_err(|e| anyhow::anyhow!("Invalid repeat format: {}", e))?;
+        let repeats_from: RepeatFrom = self
+            .repeats_from
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Invalid repeats from: {}", e))?;
+        let date = match self.date.trim() {
+            "" => None,
+            date => Some(utils::parse_date(date, settings)?),
+        };
+        let timezone: Option<Tz> = match self.timezone.trim() {
+            "" => None,
+            tz => Some(
+                tz.parse()
+                    .map_err(|_| anyhow::anyhow!("Unknown time zone '{}'", tz))?,
+            ),
+        };
+        // The time of day is in the task's time zone, if it has one
+        let date = match (date, timezone) {
+            (Some((date, true)), Some(tz)) => {
+                let naive = date.naive_local();
+                let date = tz
+                    .from_local_datetime(&naive)
+                    .earliest()
+                    .ok_or_else(|| anyhow::anyhow!("{} doesn't exist in {}", naive, tz.name()))?;
+                Some((date.with_timezone(&Local), true))
+            }
+            (date, _) => date,
+        };
+        if date.is_none() && repeat != Repeat::Never {
+            return Err(anyhow::anyhow!("Repeating tasks need a due date"));
+        }
+        let start_date = match self.start_date.trim() {
+            "" => None,
+            start => Some(
+                utils::parse_date(start, settings)
+                    .map_err(|_| anyhow::anyhow!("Invalid start date"))?
+                    .0
+                    .date_naive(),
+            ),
+        };
+        if let (Some(start), Some((date, _))) = (start_date, date) {
+            if start > date.date_naive() {
+                return Err(anyhow::anyhow!("The task can't start after it's due"));
+            }
+        }
+        let repeats_until = match self.repeats_until.trim() {
+            "" => None,
+            until => Some(
+                utils::parse_date(until, settings)
+                    .map_err(|_| anyhow::anyhow!("Invalid repeat until date"))?
+                    .0,
+            ),
+        };
+        let occurrences_left = match self.occurrences_left.trim() {
+            "" => None,
+            left => match left.parse() {
+                Ok(left) if left > 0 => Some(left),
+                _ => {
+                    return Err(anyhow::anyhow!(
+                        "The number of times left must be a positive number"
+                    ))
+                }
+            },
+        };
 
         task.set_id(self.id);
+        if let Some(series_id) = self.series_id {
+            task.series_id = series_id;
+        }
         task.set_name(self.name.clone());
-        task.set_date(date);
+        if let Some((date, has_time)) = date {
+            task.set_date(date);
+            task.set_has_time(has_time);
+        }
+        if let Some(timezone) = timezone {
+            task.set_timezone(timezone);
+        }
+        if let Some(start_date) = start_date {
+            task.set_start_date(start_date);
+        }
         task.set_repeats(repeat);
+        task.set_repeats_from(repeats_from);
+        if let Some(repeats_until) = repeats_until {
+            task.set_repeats_until(repeats_until);
+        }
+        if let Some(occurrences_left) = occurrences_left {
+            task.set_occurrences_left(occurrences_left);
+        }
         if !self.group.is_empty() {
             task.set_group(self.group.clone());
         }
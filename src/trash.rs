@@ -0,0 +1,184 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::Id,
+    error::StorageError,
+    task::{deserialize_dt, serialize_dt, Task},
+    utils::{self, FileStamp},
+};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TrashedTask {
+    #[serde(serialize_with = "serialize_dt", deserialize_with = "deserialize_dt")]
+    pub deleted_at: DateTime<Local>,
+    pub task: Task,
+}
+
+/// Deleted tasks, kept next to the tasks until they are restored or purged. Like the
+/// history, it is re-read whenever another process changed it.
+pub struct Trash {
+    /// Oldest first
+    items: Vec<TrashedTask>,
+    file: Option<PathBuf>,
+    stamp: Option<FileStamp>,
+}
+
+impl Trash {
+    pub fn in_memory() -> Trash {
+        Trash {
+            items: vec![],
+            file: None,
+            stamp: None,
+        }
+    }
+
+    /// Opens the trash, purging the tasks deleted more than `retention_days` ago
+    pub fn open(file: PathBuf, retention_days: u32) -> Result<Trash, StorageError> {
+        let mut trash = Trash {
+            file: Some(file),
+            ..Trash::in_memory()
+        };
+        trash.purge(retention_days)?;
+        Ok(trash)
+    }
+
+    /// The deleted tasks, most recently deleted first
+    pub fn list(&mut self) -> Result<Vec<TrashedTask>, StorageError> {
+        self.sync()?;
+        Ok(self.items.iter().rev().cloned().collect())
+    }
+
+    pub fn add(&mut self, task: Task) -> Result<(), StorageError> {
+        self.modify(|items| {
+            items.push(TrashedTask {
+                deleted_at: Local::now(),
+                task,
+            })
+        })
+    }
+
+    /// Takes the last deleted task with this id out of the trash
+    pub fn take(&mut self, id: Id) -> Result<Option<TrashedTask>, StorageError> {
+        self.sync()?;
+        if !self.items.iter().any(|t| t.task.id == Some(id)) {
+            return Ok(None);
+        }
+        self.modify(|items| {
+            let idx = items.iter().rposition(|t| t.task.id == Some(id))?;
+            Some(items.remove(idx))
+        })
+    }
+
+    /// Deletes every task in the trash for good, returning how many there were
+    pub fn empty(&mut self) -> Result<usize, StorageError> {
+        self.modify(|items| items.drain(..).count())
+    }
+
+    /// Deletes the tasks that have been in the trash for longer than `retention_days`
+    pub fn purge(&mut self, retention_days: u32) -> Result<usize, StorageError> {
+        let cutoff = Local::now() - Duration::days(retention_days.into());
+        self.sync()?;
+        if self.items.iter().all(|t| t.deleted_at > cutoff) {
+            return Ok(0);
+        }
+        self.modify(|items| {
+            let count = items.len();
+            items.retain(|t| t.deleted_at > cutoff);
+            count - items.len()
+        })
+    }
+
+    /// Applies `change` to the latest version of the trash and saves it, holding the
+    /// exclusive lock throughout so that changes from other processes aren't lost
+    fn modify<T>(
+        &mut self,
+        change: impl FnOnce(&mut Vec<TrashedTask>) -> T,
+    ) -> Result<T, StorageError> {
+        let Some(file) = &self.file else {
+            return Ok(change(&mut self.items));
+        };
+        let _lock = utils::lock_file(file, true)?;
+        if let Some(items) = utils::read_locked(file, &mut self.stamp)? {
+            self.items = items;
+        }
+        let result = change(&mut self.items);
+        self.stamp = utils::write_locked(file, &self.items)?;
+        Ok(result)
+    }
+
+    /// Re-reads the trash if another process changed it
+    fn sync(&mut self) -> Result<(), StorageError> {
+        if let Some(file) = &self.file {
+            if let Some(items) = utils::read_shared(file, &mut self.stamp)? {
+                self.items = items;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: Id) -> Task {
+        let mut task = Task::default();
+        task.set_id(Some(id));
+        task
+    }
+
+    fn ids(trash: &mut Trash) -> Vec<Id> {
+        let items = trash.list().unwrap();
+        items.iter().filter_map(|t| t.task.id).collect()
+    }
+
+    #[test]
+    fn changes_from_other_processes_are_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("tasks.json.trash");
+        let mut first = Trash::open(file.clone(), 30).unwrap();
+        let mut second = Trash::open(file.clone(), 30).unwrap();
+
+        first.add(task(1)).unwrap();
+        second.add(task(2)).unwrap();
+        first.add(task(3)).unwrap();
+        assert_eq!(second.take(1).unwrap().unwrap().task.id, Some(1));
+        assert!(first.take(1).unwrap().is_none());
+
+        let mut trash = Trash::open(file, 30).unwrap();
+        assert_eq!(ids(&mut trash), [3, 2]);
+    }
+
+    #[test]
+    fn emptied_tasks_dont_come_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("tasks.json.trash");
+        let mut first = Trash::open(file.clone(), 30).unwrap();
+        let mut second = Trash::open(file.clone(), 30).unwrap();
+
+        first.add(task(1)).unwrap();
+        assert_eq!(ids(&mut second), [1]);
+        assert_eq!(first.empty().unwrap(), 1);
+        second.add(task(2)).unwrap();
+
+        let mut trash = Trash::open(file, 30).unwrap();
+        assert_eq!(ids(&mut trash), [2]);
+    }
+
+    #[test]
+    fn old_tasks_are_purged() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("tasks.json.trash");
+        let mut trash = Trash::open(file.clone(), 30).unwrap();
+        trash.add(task(1)).unwrap();
+        trash.add(task(2)).unwrap();
+        trash.items[0].deleted_at = Local::now() - Duration::days(31);
+        trash.stamp = utils::write_locked(&file, &trash.items).unwrap();
+
+        assert_eq!(trash.purge(30).unwrap(), 1);
+        assert_eq!(ids(&mut Trash::open(file, 30).unwrap()), [2]);
+    }
+}
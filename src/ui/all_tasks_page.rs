@@ -1,10 +1,13 @@
 use crate::app::App;
+use crate::configuration::OverdueRepeats;
+use crate::error::StorageError;
+use crate::history::Operation;
 use crate::repeat::Repeat;
 use crate::task::Task;
 use crate::ui::Page;
 use crate::utils;
 use anyhow::Result;
-use chrono::{DateTime, Local, TimeZone};
+use chrono::{DateTime, Local};
 use itertools::Itertools;
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -19,21 +22,29 @@ use tui::{
 
 pub struct AllTasksPage {
     pub show_hidden: bool,
+    /// Whether tasks that haven't started yet are shown
+    pub show_scheduled: bool,
     pub current_id: Option<usize>,
     pub app: Rc<RefCell<App>>,
 
     current_group: Option<String>,
+    /// The overdue repeating task waiting for an answer on whether to skip its missed
+    /// occurrences
+    asking_catch_up: Option<usize>,
 }
 
 impl AllTasksPage {
-    pub fn new(app: Rc<RefCell<App>>) -> AllTasksPage {
+    pub fn new(app: Rc<RefCell<App>>) -> Result<AllTasksPage> {
         let show_hidden = app.borrow().settings.show_complete;
+        let show_scheduled = app.borrow().settings.show_scheduled;
         let current_group = app.borrow().settings.current_group.clone();
 
         let mut atp = AllTasksPage {
             show_hidden,
+            show_scheduled,
             current_id: None,
             current_group,
+            asking_catch_up: None,
             app,
         };
 
@@ -42,10 +53,10 @@ impl AllTasksPage {
             .iter()
             .any(|t| t.group == atp.current_group);
         if !any_in_group {
-            atp.set_group(None);
+            atp.set_group(None)?;
         }
 
-        atp
+        Ok(atp)
     }
 
     /// Returns the tasks that should be displayed on the page
@@ -59,6 +70,12 @@ impl AllTasksPage {
             tasks
         };
 
+        let tasks: Vec<&Task> = if !self.show_scheduled {
+            tasks.into_iter().filter(|t| !t.is_scheduled()).collect()
+        } else {
+            tasks
+        };
+
         // Filter out tasks not in the current group
         let tasks: Vec<&Task> = if let Some(group) = &self.current_group {
             tasks
@@ -73,19 +90,20 @@ impl AllTasksPage {
         tasks
             .into_iter()
             .cloned()
-            .sorted_by(|a, b| a.date.cmp(&b.date))
+            .sorted_by(|a, b| a.cmp_by_date(b))
             .collect()
     }
 
-    pub fn ensure_group_exists(&mut self) {
+    pub fn ensure_group_exists(&mut self) -> Result<()> {
         // Check that there are still visible tasks in group
         let any = self
             .visible_tasks()
             .iter()
             .any(|t| t.group == self.get_current_group());
         if !any {
-            self.set_group(None);
+            self.set_group(None)?;
         }
+        Ok(())
     }
 
     pub fn ensure_task_exists(&mut self) {
@@ -98,16 +116,116 @@ impl AllTasksPage {
         }
     }
 
-    /// Toggles the complete status of the currently selected task
-    pub fn toggle_selected(&mut self) {
-        if let Some(task_id) = self.current_id {
-            self.app.borrow_mut().toggle_complete_task(task_id);
+    /// Picks up the filters from the settings after they were changed on disk
+    pub fn sync_with_settings(&mut self) {
+        let settings = &self.app.borrow().settings;
+        self.show_hidden = settings.show_complete;
+        self.show_scheduled = settings.show_scheduled;
+        self.current_group = settings.current_group.clone();
+    }
+
+    /// Keeps the selected task and group after a reload, unless they are gone
+    pub fn refresh(&mut self) -> Result<()> {
+        self.ensure_task_exists();
+        self.ensure_group_exists()
+    }
+
+    /// Reverts the last operation and says what happened in the title
+    pub fn undo(&mut self) -> Result<()> {
+        let result = self.app.borrow_mut().undo();
+        self.report(result, "Undid", "Nothing to undo");
+        self.refresh()
+    }
+
+    /// Applies the last undone operation again and says what happened in the title
+    pub fn redo(&mut self) -> Result<()> {
+        let result = self.app.borrow_mut().redo();
+        self.report(result, "Redid", "Nothing to redo");
+        self.refresh()
+    }
+
+    fn report(
+        &mut self,
+        result: Result<Option<Operation>, StorageError>,
+        done: &str,
+        nothing: &str,
+    ) {
+        let notice = match result {
+            Ok(Some(operation)) => format!("{} {}", done, operation),
+            Ok(None) => nothing.to_string(),
+            Err(e) => e.to_string(),
+        };
+        self.app.borrow_mut().notice = Some(notice);
+    }
+
+    /// Toggles the complete status of the currently selected task. With the `ask`
+    /// overdue policy, completing a task with missed occurrences asks what to do first
+    pub fn toggle_selected(&mut self) -> Result<()> {
+        let Some(task_id) = self.current_id else {
+            return self.refresh();
+        };
+        let (policy, missed) = {
+            let app = self.app.borrow();
+            let missed = app
+                .get_task(task_id)
+                .filter(|t| !t.complete)
+                .map(|t| t.missed_occurrences())
+                .unwrap_or(0);
+            (app.settings.overdue_repeats, missed)
+        };
 
-            if !self.show_hidden {
-                self.move_closest();
+        match policy {
+            OverdueRepeats::Ask if missed > 0 => {
+                self.asking_catch_up = Some(task_id);
+                self.app.borrow_mut().notice = Some(format!(
+                    "The next {} occurrence(s) are overdue too. Skip them? (y/n)",
+                    missed
+                ));
+                Ok(())
             }
+            policy => self.toggle(task_id, policy == OverdueRepeats::SkipToNextFuture),
+        }
+    }
+
+    pub fn is_asking_catch_up(&self) -> bool {
+        self.asking_catch_up.is_some()
+    }
+
+    /// Completes the task that asked whether to skip its missed occurrences, or leaves it
+    /// as it was if there is no answer
+    pub fn answer_catch_up(&mut self, catch_up: Option<bool>) -> Result<()> {
+        match (self.asking_catch_up.take(), catch_up) {
+            (Some(task_id), Some(catch_up)) => self.toggle(task_id, catch_up),
+            _ => Ok(()),
+        }
+    }
+
+    fn toggle(&mut self, task_id: usize, catch_up: bool) -> Result<()> {
+        self.app
+            .borrow_mut()
+            .toggle_complete_task(task_id, catch_up)?;
+
+        if !self.show_hidden {
+            self.move_closest();
+        }
+        self.refresh()
+    }
+
+    /// Moves the selected repeating task on to its next occurrence without completing it
+    pub fn skip_selected(&mut self) -> Result<()> {
+        if let Some(task_id) = self.current_id {
+            let mut app = self.app.borrow_mut();
+            let skipped = app.skip_occurrence(task_id)?;
+            let notice = match skipped.and_then(|id| app.get_task(id)) {
+                Some(task) => format!(
+                    "Skipped to {}",
+                    utils::due_to_display_str(task, &app.settings)
+                ),
+                None => "The task has no next occurrence to skip to".to_string(),
+            };
+            app.notice = Some(notice);
         }
-        self.ensure_group_exists();
+        self.refresh()
     }
 
     pub fn next(&mut self) {
@@ -148,7 +266,7 @@ impl AllTasksPage {
         self.current_group.clone()
     }
 
-    pub fn set_group(&mut self, group: Option<String>) {
+    pub fn set_group(&mut self, group: Option<String>) -> Result<()> {
         self.current_group = group.clone();
         if self.current_id.is_some() {
             let id = self.current_id.unwrap();
@@ -157,10 +275,11 @@ impl AllTasksPage {
                 self.current_id = None;
             }
         }
-        self.app.borrow_mut().settings.set_current_group(group);
+        self.app.borrow_mut().settings.set_current_group(group)?;
+        Ok(())
     }
 
-    pub fn next_group(&mut self) {
+    pub fn next_group(&mut self) -> Result<()> {
         let groups = self.get_groups();
         self.current_id = None;
         match &self.current_group {
@@ -179,37 +298,36 @@ impl AllTasksPage {
         self.app
             .borrow_mut()
             .settings
-            .set_current_group(self.current_group.clone());
+            .set_current_group(self.current_group.clone())?;
+        Ok(())
     }
 
-    pub fn prev_group(&mut self) {
+    pub fn prev_group(&mut self) -> Result<()> {
         let groups = self.get_groups();
         self.current_id = None;
-        match &self.current_group {
-            Some(group) => {
-                let idx = groups.iter().position(|g| g == group).unwrap();
-                if idx > 1 {
-                    self.current_group = Some(groups[idx - 1].clone());
-                } else {
-                    self.current_group = None;
-                }
+        if let Some(group) = &self.current_group {
+            let idx = groups.iter().position(|g| g == group).unwrap();
+            if idx > 1 {
+                self.current_group = Some(groups[idx - 1].clone());
+            } else {
+                self.current_group = None;
             }
-            None => {}
         }
         self.app
             .borrow_mut()
             .settings
-            .set_current_group(self.current_group.clone());
+            .set_current_group(self.current_group.clone())?;
+        Ok(())
     }
 
     pub fn groups(&self) -> Vec<Vec<Task>> {
         self.visible_tasks()
             .into_iter()
-            .group_by(|t| t.date.date_naive())
+            .group_by(|t| t.date.map(|d| d.date_naive()))
             .into_iter()
             .map(|(_, group)| {
                 group
-                    .sorted_by(|a, b| a.date.cmp(&b.date))
+                    .sorted_by(|a, b| a.cmp_by_date(b))
                     .collect::<Vec<Task>>()
             })
             .collect()
@@ -218,7 +336,7 @@ impl AllTasksPage {
     pub fn move_closest(&mut self) {
         let current_date: Option<DateTime<Local>> = {
             match self.current_id {
-                Some(id) => self.app.borrow().get_task(id).map(|t| t.date),
+                Some(id) => self.app.borrow().get_task(id).and_then(|t| t.date),
                 None => None,
             }
         };
@@ -228,9 +346,8 @@ impl AllTasksPage {
         let current_date = current_date.unwrap_or_else(Local::now);
         let closest = tasks.iter().min_by_key(|t| {
             t.date
-                .signed_duration_since(current_date)
-                .num_seconds()
-                .abs()
+                .map(|d| d.signed_duration_since(current_date).num_seconds().abs())
+                .unwrap_or(i64::MAX)
         });
         match closest {
             Some(task) => self.current_id = Some(task.id.unwrap()),
@@ -238,16 +355,30 @@ impl AllTasksPage {
         }
     }
 
-    pub fn toggle_hidden(&mut self) {
+    pub fn toggle_hidden(&mut self) -> Result<()> {
         self.show_hidden = !self.show_hidden;
         self.app
             .borrow_mut()
             .settings
-            .set_show_complete(self.show_hidden);
-        self.ensure_group_exists();
+            .set_show_complete(self.show_hidden)?;
+        self.ensure_group_exists()?;
         if !self.show_hidden {
             self.move_closest();
         }
+        Ok(())
+    }
+
+    pub fn toggle_scheduled(&mut self) -> Result<()> {
+        self.show_scheduled = !self.show_scheduled;
+        self.app
+            .borrow_mut()
+            .settings
+            .set_show_scheduled(self.show_scheduled)?;
+        self.ensure_group_exists()?;
+        if !self.show_scheduled {
+            self.move_closest();
+        }
+        Ok(())
     }
 
     pub fn get_groups(&self) -> Vec<String> {
@@ -259,6 +390,11 @@ impl AllTasksPage {
         } else {
             tasks
         };
+        let tasks: Vec<Task> = if !self.show_scheduled {
+            tasks.into_iter().filter(|t| !t.is_scheduled()).collect()
+        } else {
+            tasks
+        };
         let mut other_groups = tasks
             .iter()
             .filter_map(|t| t.group.clone())
@@ -279,8 +415,14 @@ impl AllTasksPage {
         }
     }
 
+    /// The day of `date`, without its time. Humanized days keep the date next to them
     pub fn date_to_str(&self, date: &DateTime<Local>) -> String {
-        utils::date_to_display_str(date, &self.app.borrow().settings)
+        let settings = &self.app.borrow().settings;
+        let day = utils::date_to_display_str(date, false, settings);
+        match utils::due_to_human_str(date, false, settings) {
+            human if human != day => format!("{} · {}", human, day),
+            _ => day,
+        }
     }
 
     pub fn open_selected_link(&self) -> Result<()> {
@@ -351,9 +493,11 @@ impl Page for AllTasksPage {
         let mut rows = vec![];
         for group in self.groups() {
             // Group title
-            let group_date = &group[0].date.date_naive().and_hms_opt(23, 59, 59).unwrap();
-            let group_date = Local.from_local_datetime(group_date).unwrap();
-            let date_str = self.date_to_str(&group_date).to_uppercase();
+            let date_str = match &group[0].date {
+                Some(date) => self.date_to_str(date),
+                None => "No date".to_string(),
+            }
+            .to_uppercase();
             let group_title = " ".to_string() + date_str.as_str();
             let cell = Cell::from(Span::styled(
                 group_title,
@@ -380,6 +524,7 @@ impl Page for AllTasksPage {
                         .fg(self.get_secondary_color())
                         .add_modifier(Modifier::BOLD),
                     (true, _) => Style::default().fg(Color::DarkGray),
+                    _ if item.is_scheduled() => Style::default().fg(Color::DarkGray),
                     _ => Style::default().fg(Color::White),
                 };
                 let title_style = title_style.add_modifier(Modifier::BOLD);
@@ -410,10 +555,17 @@ impl Page for AllTasksPage {
             true => BorderType::Thick,
             false => BorderType::Plain,
         };
+        let mut title = vec![Span::raw("Todos")];
+        if let Some(notice) = &self.app.borrow().notice {
+            title.push(Span::styled(
+                format!(" - {}", notice),
+                Style::default().fg(self.get_secondary_color()),
+            ));
+        }
         let list = Table::new(rows, &[Constraint::Percentage(100)]).block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Todos")
+                .title(Line::from(title))
                 .border_style(border_style)
                 .border_type(border_type),
         );
@@ -1,6 +1,7 @@
 use crate::{
     app::{App, Id},
     configuration::KeyBindings,
+    error::StorageError,
     key,
 };
 use std::{cell::RefCell, rc::Rc};
@@ -35,16 +36,17 @@ impl DeleteTaskPage {
     }
 
     pub fn get_task_name(&self) -> String {
+        // The task might have been deleted by another process in the meantime
         self.app
             .borrow()
             .get_task(self.task_id)
-            .unwrap()
-            .name
-            .clone()
+            .map(|t| t.name.clone())
+            .unwrap_or_default()
     }
 
-    pub fn remove_task(&self) {
-        self.app.borrow_mut().delete_task(self.task_id);
+    pub fn remove_task(&self) -> Result<(), StorageError> {
+        self.app.borrow_mut().delete_task(self.task_id)?;
+        Ok(())
     }
 
     pub fn add_char(&mut self, c: char) {
@@ -57,8 +59,13 @@ impl DeleteTaskPage {
 
     pub fn submit(&mut self) -> bool {
         if self.task_form == self.get_task_name() {
-            self.remove_task();
-            true
+            match self.remove_task() {
+                Ok(()) => true,
+                Err(e) => {
+                    self.error = Some(e.to_string());
+                    false
+                }
+            }
         } else {
             self.error = Some(format!(
                 "The name you entered is not the same as the task name: '{}'",
@@ -68,7 +75,7 @@ impl DeleteTaskPage {
         }
     }
 
-    fn get_keybind_hint(&self) -> Line {
+    fn get_keybind_hint(&self) -> Line<'_> {
         let color = self.get_secondary_color();
         let kb = &self.app.borrow().settings.keybindings;
         let i = key!(kb.enter_insert_mode, color);
@@ -1,4 +1,4 @@
-use crate::app::App;
+use crate::{app::App, error::StorageError};
 use anyhow::Result;
 use crossterm::{
     cursor::SetCursorStyle,
@@ -8,6 +8,7 @@ use crossterm::{
 };
 use std::io::stdout;
 use std::rc::Rc;
+use std::time::Duration;
 use std::{cell::RefCell, io::Write};
 use tui::{
     backend::{Backend, CrosstermBackend},
@@ -18,10 +19,12 @@ use tui::{
 mod all_tasks_page;
 mod delete_task_page;
 mod task_page;
+mod trash_page;
 
 use all_tasks_page::AllTasksPage;
 use delete_task_page::DeleteTaskPage;
 use task_page::TaskPage;
+use trash_page::TrashPage;
 
 #[macro_export]
 macro_rules! key {
@@ -45,7 +48,7 @@ pub fn start_ui(app: App) -> Result<()> {
             .expect("Failed to reset cursor style");
     })?;
 
-    run_app(&mut terminal, app)?;
+    let res = run_app(&mut terminal, app);
 
     // restore terminal
     disable_raw_mode()?;
@@ -57,7 +60,7 @@ pub fn start_ui(app: App) -> Result<()> {
     )?;
     terminal.show_cursor()?;
 
-    Ok(())
+    res
 }
 
 #[derive(Eq, PartialEq)]
@@ -66,6 +69,7 @@ pub enum UIPage {
     NewTask,
     EditTask,
     DeleteTask,
+    Trash,
 }
 
 #[derive(Eq, PartialEq)]
@@ -74,166 +78,289 @@ pub enum InputMode {
     Normal,
 }
 
+/// How long to wait for a key press before checking whether the files changed on disk
+const RELOAD_INTERVAL: Duration = Duration::from_millis(500);
+
 pub trait Page {
     fn ui(&self, f: &mut Frame, area: Rect, focused: bool);
 }
 
+/// Everything the main loop keeps between events
+struct State {
+    app: Rc<RefCell<App>>,
+    current_page: UIPage,
+    all_tasks_page: AllTasksPage,
+    task_page: TaskPage,
+    delete_task_page: Option<DeleteTaskPage>,
+    trash_page: Option<TrashPage>,
+}
+
 fn run_app<B: Backend + Write>(terminal: &mut Terminal<B>, app: App) -> Result<()> {
     let app = Rc::new(RefCell::new(app));
-    let mut all_tasks_page = AllTasksPage::new(Rc::clone(&app));
-    let mut task_page = TaskPage::new(Rc::clone(&app));
-    let mut current_page = UIPage::AllTasks;
-    let mut delete_task_page = None;
+    let mut state = State {
+        all_tasks_page: AllTasksPage::new(Rc::clone(&app))?,
+        task_page: TaskPage::new(Rc::clone(&app)),
+        current_page: UIPage::AllTasks,
+        delete_task_page: None,
+        trash_page: None,
+        app,
+    };
 
     loop {
         terminal.draw(|f| {
             render_app(
                 f,
-                &mut all_tasks_page,
-                &mut task_page,
-                &mut delete_task_page,
-                &current_page,
+                &mut state.all_tasks_page,
+                &mut state.task_page,
+                &mut state.delete_task_page,
+                &mut state.trash_page,
+                &state.current_page,
             )
         })?;
-        let keybindings = &app.borrow().settings.keybindings.clone();
 
-        if let Event::Key(key) = event::read()? {
-            let code = key.code;
-            match current_page {
-                UIPage::AllTasks => match code {
-                    _ if code == keybindings.quit => break,
-                    _ if code == keybindings.down => {
-                        all_tasks_page.next();
-                        if let Some(task_id) = all_tasks_page.current_id {
-                            task_page = TaskPage::new_from_task(Rc::clone(&app), task_id);
-                        }
-                    }
-                    _ if code == keybindings.up => {
-                        all_tasks_page.prev();
-                        if let Some(task_id) = all_tasks_page.current_id {
-                            task_page = TaskPage::new_from_task(Rc::clone(&app), task_id);
-                        }
-                    }
-                    _ if code == keybindings.complete_task => {
-                        all_tasks_page.toggle_selected();
-                    }
-                    _ if code == keybindings.toggle_completed_tasks => {
-                        all_tasks_page.toggle_hidden()
-                    }
-                    _ if code == keybindings.delete_task => {
-                        if let Some(task_id) = all_tasks_page.current_id {
-                            delete_task_page = Some(DeleteTaskPage::new(Rc::clone(&app), task_id));
-                            current_page = UIPage::DeleteTask;
-                        }
+        match handle_event(terminal, &mut state) {
+            Ok(true) => break,
+            Ok(false) => {}
+            // Keep the UI running when the files can't be read or written, so the
+            // user can fix them and try again
+            Err(e) => match e.downcast::<StorageError>() {
+                Ok(e) => state.app.borrow_mut().notice = Some(e.to_string()),
+                Err(e) => return Err(e),
+            },
+        }
+    }
+
+    Ok(())
+}
+
+/// Waits for the next key press or reload and handles it. Returns whether to quit
+fn handle_event<B: Backend + Write>(terminal: &mut Terminal<B>, state: &mut State) -> Result<bool> {
+    let State {
+        app,
+        current_page,
+        all_tasks_page,
+        task_page,
+        delete_task_page,
+        trash_page,
+    } = state;
+    let keybindings = &app.borrow().settings.keybindings.clone();
+
+    if !event::poll(RELOAD_INTERVAL)? {
+        let (tasks, settings) = {
+            let mut app = app.borrow_mut();
+            let tasks = app.reload_if_changed()?;
+            (tasks, app.settings.reload_if_changed()?)
+        };
+        if !tasks && !settings {
+            return Ok(false);
+        }
+
+        if settings {
+            all_tasks_page.sync_with_settings();
+        }
+        all_tasks_page.refresh()?;
+        if let Some(trash_page) = trash_page.as_mut() {
+            trash_page.refresh()?;
+        }
+        let selected = all_tasks_page.current_id;
+        match current_page {
+            UIPage::AllTasks => {
+                if let Some(task_id) = selected {
+                    *task_page = TaskPage::new_from_task(Rc::clone(app), task_id);
+                }
+            }
+            // Don't throw away the user's edits, unless the task is gone
+            UIPage::EditTask | UIPage::DeleteTask if selected.is_none() => {
+                *current_page = UIPage::AllTasks;
+                *delete_task_page = None;
+                set_cursor(terminal, SetCursorStyle::SteadyBlock)?;
+            }
+            _ => {}
+        }
+        return Ok(false);
+    }
+
+    if let Event::Key(key) = event::read()? {
+        let code = key.code;
+        app.borrow_mut().notice = None;
+        match current_page {
+            UIPage::AllTasks => match code {
+                _ if all_tasks_page.is_asking_catch_up() => {
+                    let catch_up = match code {
+                        KeyCode::Char('y') => Some(true),
+                        KeyCode::Char('n') => Some(false),
+                        _ => None,
+                    };
+                    all_tasks_page.answer_catch_up(catch_up)?;
+                }
+                _ if code == keybindings.quit => return Ok(true),
+                _ if code == keybindings.down => {
+                    all_tasks_page.next();
+                    if let Some(task_id) = all_tasks_page.current_id {
+                        *task_page = TaskPage::new_from_task(Rc::clone(app), task_id);
                     }
-                    _ if code == keybindings.open_link => all_tasks_page.open_selected_link()?,
-                    _ if code == keybindings.new_task => {
-                        current_page = UIPage::NewTask;
-                        task_page = TaskPage::new(Rc::clone(&app));
+                }
+                _ if code == keybindings.up => {
+                    all_tasks_page.prev();
+                    if let Some(task_id) = all_tasks_page.current_id {
+                        *task_page = TaskPage::new_from_task(Rc::clone(app), task_id);
                     }
-                    _ if code == keybindings.edit_task => {
-                        if all_tasks_page.current_id.is_some() {
-                            current_page = UIPage::EditTask;
-                        }
+                }
+                _ if code == keybindings.complete_task => {
+                    all_tasks_page.toggle_selected()?;
+                }
+                _ if code == keybindings.toggle_completed_tasks => {
+                    all_tasks_page.toggle_hidden()?
+                }
+                _ if code == keybindings.toggle_scheduled_tasks => {
+                    all_tasks_page.toggle_scheduled()?
+                }
+                _ if code == keybindings.delete_task => {
+                    if let Some(task_id) = all_tasks_page.current_id {
+                        *delete_task_page = Some(DeleteTaskPage::new(Rc::clone(app), task_id));
+                        *current_page = UIPage::DeleteTask;
                     }
-                    _ if code == keybindings.next_group => {
-                        all_tasks_page.next_group();
+                }
+                _ if code == keybindings.open_link => all_tasks_page.open_selected_link()?,
+                _ if code == keybindings.new_task => {
+                    *current_page = UIPage::NewTask;
+                    *task_page = TaskPage::new(Rc::clone(app));
+                }
+                _ if code == keybindings.edit_task && all_tasks_page.current_id.is_some() => {
+                    *current_page = UIPage::EditTask;
+                }
+                _ if code == keybindings.next_group => {
+                    all_tasks_page.next_group()?;
+                }
+                _ if code == keybindings.prev_group => {
+                    all_tasks_page.prev_group()?;
+                }
+                _ if code == keybindings.undo => {
+                    all_tasks_page.undo()?;
+                    if let Some(task_id) = all_tasks_page.current_id {
+                        *task_page = TaskPage::new_from_task(Rc::clone(app), task_id);
                     }
-                    _ if code == keybindings.prev_group => {
-                        all_tasks_page.prev_group();
+                }
+                _ if code == keybindings.redo => {
+                    all_tasks_page.redo()?;
+                    if let Some(task_id) = all_tasks_page.current_id {
+                        *task_page = TaskPage::new_from_task(Rc::clone(app), task_id);
                     }
-                    _ => {}
-                },
-                UIPage::DeleteTask => {
-                    let dtp = delete_task_page.as_mut().unwrap();
-                    match dtp.input_mode {
-                        InputMode::Normal => match key.code {
-                            _ if code == keybindings.quit => break,
-                            _ if code == keybindings.enter_insert_mode => {
-                                dtp.input_mode = InputMode::Insert;
-                                set_cursor(terminal, SetCursorStyle::SteadyBar)?;
-                            }
-                            _ if code == keybindings.go_back => {
-                                current_page = UIPage::AllTasks;
-                                set_cursor(terminal, SetCursorStyle::SteadyBlock)?;
-                            }
-                            _ if code == keybindings.save_changes => {
-                                if dtp.submit() {
-                                    all_tasks_page.ensure_group_exists();
-                                    all_tasks_page.ensure_task_exists();
-                                    current_page = UIPage::AllTasks;
-                                    delete_task_page = None;
-                                    set_cursor(terminal, SetCursorStyle::SteadyBlock)?;
-                                }
-                            }
-                            _ => {}
-                        },
-                        InputMode::Insert => match key.code {
-                            _ if code == keybindings.enter_normal_mode => {
-                                dtp.input_mode = InputMode::Normal;
-                                set_cursor(terminal, SetCursorStyle::SteadyBlock)?;
-                            }
-                            _ if code == keybindings.save_changes => {
-                                if dtp.submit() {
-                                    all_tasks_page.ensure_group_exists();
-                                    all_tasks_page.ensure_task_exists();
-                                    current_page = UIPage::AllTasks;
-                                    delete_task_page = None;
-                                    set_cursor(terminal, SetCursorStyle::SteadyBlock)?;
-                                }
-                            }
-                            KeyCode::Char(c) => dtp.add_char(c),
-                            KeyCode::Backspace => dtp.remove_char(),
-                            _ => {}
-                        },
+                }
+                _ if code == keybindings.skip_occurrence => {
+                    all_tasks_page.skip_selected()?;
+                    if let Some(task_id) = all_tasks_page.current_id {
+                        *task_page = TaskPage::new_from_task(Rc::clone(app), task_id);
                     }
                 }
-                UIPage::NewTask | UIPage::EditTask => match task_page.input_mode {
+                _ if code == keybindings.open_trash => {
+                    *trash_page = Some(TrashPage::new(Rc::clone(app))?);
+                    *current_page = UIPage::Trash;
+                }
+                _ => {}
+            },
+            UIPage::DeleteTask => {
+                let dtp = delete_task_page.as_mut().unwrap();
+                match dtp.input_mode {
                     InputMode::Normal => match key.code {
-                        _ if code == keybindings.down => task_page.next_field(),
-                        _ if code == keybindings.up => task_page.prev_field(),
-                        _ if code == keybindings.quit => break,
+                        _ if code == keybindings.quit => return Ok(true),
                         _ if code == keybindings.enter_insert_mode => {
-                            task_page.input_mode = InputMode::Insert;
+                            dtp.input_mode = InputMode::Insert;
                             set_cursor(terminal, SetCursorStyle::SteadyBar)?;
                         }
                         _ if code == keybindings.go_back => {
-                            current_page = UIPage::AllTasks;
+                            *current_page = UIPage::AllTasks;
                             set_cursor(terminal, SetCursorStyle::SteadyBlock)?;
                         }
-                        _ if code == keybindings.save_changes => {
-                            if task_page.submit() {
-                                all_tasks_page.ensure_group_exists();
-                                all_tasks_page.ensure_task_exists();
-                                current_page = UIPage::AllTasks;
-                                set_cursor(terminal, SetCursorStyle::SteadyBlock)?;
-                            }
+                        _ if code == keybindings.save_changes && dtp.submit() => {
+                            all_tasks_page.ensure_group_exists()?;
+                            all_tasks_page.ensure_task_exists();
+                            *current_page = UIPage::AllTasks;
+                            *delete_task_page = None;
+                            set_cursor(terminal, SetCursorStyle::SteadyBlock)?;
                         }
                         _ => {}
                     },
                     InputMode::Insert => match key.code {
                         _ if code == keybindings.enter_normal_mode => {
-                            task_page.input_mode = InputMode::Normal;
+                            dtp.input_mode = InputMode::Normal;
                             set_cursor(terminal, SetCursorStyle::SteadyBlock)?;
                         }
-                        _ if code == keybindings.save_changes => {
-                            if task_page.submit() {
-                                all_tasks_page.ensure_group_exists();
-                                all_tasks_page.ensure_task_exists();
-                                current_page = UIPage::AllTasks;
-                                set_cursor(terminal, SetCursorStyle::SteadyBlock)?;
-                            }
+                        _ if code == keybindings.save_changes && dtp.submit() => {
+                            all_tasks_page.ensure_group_exists()?;
+                            all_tasks_page.ensure_task_exists();
+                            *current_page = UIPage::AllTasks;
+                            *delete_task_page = None;
+                            set_cursor(terminal, SetCursorStyle::SteadyBlock)?;
                         }
-                        KeyCode::Char(c) => task_page.add_char(c),
-                        KeyCode::Backspace => task_page.remove_char(),
+                        KeyCode::Char(c) => dtp.add_char(c),
+                        KeyCode::Backspace => dtp.remove_char(),
                         _ => {}
                     },
-                },
+                }
             }
+            UIPage::Trash => {
+                let tp = trash_page.as_mut().unwrap();
+                if code != keybindings.empty_trash {
+                    tp.cancel_empty();
+                }
+                match code {
+                    _ if code == keybindings.quit => return Ok(true),
+                    _ if code == keybindings.down => tp.next(),
+                    _ if code == keybindings.up => tp.prev(),
+                    _ if code == keybindings.restore_task => {
+                        tp.restore_selected()?;
+                        all_tasks_page.refresh()?;
+                    }
+                    _ if code == keybindings.empty_trash => tp.empty()?,
+                    _ if code == keybindings.go_back => {
+                        *current_page = UIPage::AllTasks;
+                        *trash_page = None;
+                    }
+                    _ => {}
+                }
+            }
+            UIPage::NewTask | UIPage::EditTask => match task_page.input_mode {
+                InputMode::Normal => match key.code {
+                    _ if code == keybindings.down => task_page.next_field(),
+                    _ if code == keybindings.up => task_page.prev_field(),
+                    _ if code == keybindings.quit => return Ok(true),
+                    _ if code == keybindings.enter_insert_mode => {
+                        task_page.input_mode = InputMode::Insert;
+                        set_cursor(terminal, SetCursorStyle::SteadyBar)?;
+                    }
+                    _ if code == keybindings.go_back => {
+                        *current_page = UIPage::AllTasks;
+                        set_cursor(terminal, SetCursorStyle::SteadyBlock)?;
+                    }
+                    _ if code == keybindings.save_changes && task_page.submit() => {
+                        all_tasks_page.ensure_group_exists()?;
+                        all_tasks_page.ensure_task_exists();
+                        *current_page = UIPage::AllTasks;
+                        set_cursor(terminal, SetCursorStyle::SteadyBlock)?;
+                    }
+                    _ => {}
+                },
+                InputMode::Insert => match key.code {
+                    _ if code == keybindings.enter_normal_mode => {
+                        task_page.input_mode = InputMode::Normal;
+                        set_cursor(terminal, SetCursorStyle::SteadyBlock)?;
+                    }
+                    _ if code == keybindings.save_changes && task_page.submit() => {
+                        all_tasks_page.ensure_group_exists()?;
+                        all_tasks_page.ensure_task_exists();
+                        *current_page = UIPage::AllTasks;
+                        set_cursor(terminal, SetCursorStyle::SteadyBlock)?;
+                    }
+                    KeyCode::Char(c) => task_page.add_char(c),
+                    KeyCode::Backspace => task_page.remove_char(),
+                    _ => {}
+                },
+            },
         }
     }
 
-    Ok(())
+    Ok(false)
 }
 
 fn render_app(
@@ -241,6 +368,7 @@ fn render_app(
     all_tasks_page: &mut AllTasksPage,
     task_page: &mut TaskPage,
     delete_task_page: &mut Option<DeleteTaskPage>,
+    trash_page: &mut Option<TrashPage>,
     current_page: &UIPage,
 ) {
     let constraints = match (current_page, all_tasks_page.current_id) {
@@ -261,6 +389,9 @@ fn render_app(
         UIPage::DeleteTask => {
             delete_task_page.as_mut().unwrap().ui(f, chunks[0], true);
         }
+        UIPage::Trash => {
+            trash_page.as_mut().unwrap().ui(f, chunks[0], true);
+        }
         _ => match all_tasks_page.current_id {
             Some(_) => {
                 all_tasks_page.ui(f, chunks[0], current_page == &UIPage::AllTasks);
@@ -1,4 +1,11 @@
-use crate::{app::App, configuration::KeyBindings, key, task_form::TaskForm};
+use crate::{
+    app::App,
+    configuration::KeyBindings,
+    key,
+    repeat::Repeat,
+    task_form::{self, TaskForm},
+    utils,
+};
 use std::{cell::RefCell, rc::Rc};
 use tui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
@@ -28,7 +35,7 @@ impl TaskPage {
             input_mode: InputMode::Normal,
             current_idx: 0,
             error: None,
-            num_fields: 6,
+            num_fields: 11,
             editing_task: None,
             app,
         }
@@ -43,7 +50,7 @@ impl TaskPage {
             input_mode: InputMode::Normal,
             current_idx: 0,
             error: None,
-            num_fields: 6,
+            num_fields: 11,
             editing_task: Some(task_id),
             app,
         }
@@ -65,10 +72,15 @@ impl TaskPage {
         match self.current_idx {
             0 => self.task_form.name.push(c),
             1 => self.task_form.date.push(c),
-            2 => self.task_form.repeats.push(c),
-            3 => self.task_form.group.push(c),
-            4 => self.task_form.description.push(c),
-            5 => self.task_form.url.push(c),
+            2 => self.task_form.timezone.push(c),
+            3 => self.task_form.start_date.push(c),
+            4 => self.task_form.repeats.push(c),
+            5 => self.task_form.repeats_from.push(c),
+            6 => self.task_form.repeats_until.push(c),
+            7 => self.task_form.occurrences_left.push(c),
+            8 => self.task_form.group.push(c),
+            9 => self.task_form.description.push(c),
+            10 => self.task_form.url.push(c),
             _ => {}
         };
     }
@@ -77,10 +89,15 @@ impl TaskPage {
         match self.current_idx {
             0 => self.task_form.name.pop(),
             1 => self.task_form.date.pop(),
-            2 => self.task_form.repeats.pop(),
-            3 => self.task_form.group.pop(),
-            4 => self.task_form.description.pop(),
-            5 => self.task_form.url.pop(),
+            2 => self.task_form.timezone.pop(),
+            3 => self.task_form.start_date.pop(),
+            4 => self.task_form.repeats.pop(),
+            5 => self.task_form.repeats_from.pop(),
+            6 => self.task_form.repeats_until.pop(),
+            7 => self.task_form.occurrences_left.pop(),
+            8 => self.task_form.group.pop(),
+            9 => self.task_form.description.pop(),
+            10 => self.task_form.url.pop(),
             _ => None,
         };
     }
@@ -89,14 +106,23 @@ impl TaskPage {
         let mut app = self.app.borrow_mut();
         let settings = &app.settings;
         let form_result = self.task_form.submit(settings);
-        match form_result {
-            Ok(new_task) => {
-                if let Some(task_id) = self.editing_task {
-                    app.delete_task(task_id);
+        let result = form_result.and_then(|mut new_task| {
+            // The form doesn't cover the completion history, so keep the existing one
+            if let Some(task) = self.editing_task.and_then(|id| app.get_task(id)) {
+                new_task.completions = task.completions.clone();
+                new_task.earlier_completions = task.earlier_completions.clone();
+                if new_task.date == task.date {
+                    new_task.anchor = task.anchor;
                 }
-                app.add_task(new_task);
-                true
             }
+            // An edited task that was deleted in the meantime is added back
+            if self.editing_task.is_none() || app.update_task(new_task.clone())?.is_none() {
+                app.add_task(new_task)?;
+            }
+            Ok(())
+        });
+        match result {
+            Ok(()) => true,
             Err(e) => {
                 self.error = Some(e.to_string());
                 false
@@ -104,6 +130,79 @@ impl TaskPage {
         }
     }
 
+    /// The streak and past completions of the repeating task being edited
+    fn get_history(&self) -> Option<Text<'_>> {
+        let app = self.app.borrow();
+        let task = app.get_task(self.editing_task?)?;
+        if task.repeats == Repeat::Never && task.completions.is_empty() {
+            return None;
+        }
+
+        let mut lines = vec![Line::from(format!(
+            "Streak: {} (best {})",
+            task.streak(),
+            task.best_streak()
+        ))];
+        for completion in task.completions.iter().rev() {
+            let completed_at =
+                utils::past_to_human_str(&completion.completed_at, true, &app.settings);
+            let status = match completion.on_time {
+                true => Span::styled("on time", Style::default().fg(self.get_primary_color())),
+                false => Span::styled(
+                    format!(
+                        "late, was due {}",
+                        utils::date_to_display_str(&completion.due, task.has_time, &app.settings)
+                    ),
+                    Style::default().fg(Color::Red),
+                ),
+            };
+            lines.push(Line::from(vec![
+                Span::raw(format!("{} - ", completed_at)),
+                status,
+            ]));
+        }
+        Some(Text::from(lines))
+    }
+
+    /// When the task is due, relative to today, if dates are humanized
+    fn get_relative_date(&self) -> Option<Line<'_>> {
+        let settings = &self.app.borrow().settings;
+        if !settings.humanize_dates || self.task_form.date.trim().is_empty() {
+            return None;
+        }
+        let task = self.task_form.parse(settings).ok()?;
+        Some(Line::styled(
+            format!(" {} ", utils::due_to_display_str(&task, settings)),
+            Style::default().fg(Color::DarkGray),
+        ))
+    }
+
+    /// The next due dates of a repeating task, or why they can't be worked out
+    fn get_preview(&self) -> Option<Line<'_>> {
+        if self.task_form.repeats.trim().is_empty() {
+            return None;
+        }
+        let settings = &self.app.borrow().settings;
+        match self.task_form.parse(settings) {
+            Ok(task) => {
+                let dates = task
+                    .due_dates()
+                    .take(task_form::PREVIEW_LEN)
+                    .map(|date| utils::date_to_display_str(&date, task.has_time, settings))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                Some(Line::styled(
+                    format!(" Next: {} ", dates),
+                    Style::default().fg(Color::DarkGray),
+                ))
+            }
+            Err(e) => Some(Line::styled(
+                format!(" {} ", e),
+                Style::default().fg(Color::Red),
+            )),
+        }
+    }
+
     fn border_style(&self, idx: usize) -> Style {
         if self.current_idx == idx && self.input_mode == InputMode::Insert {
             Style::default().fg(self.get_primary_color())
@@ -127,10 +226,13 @@ impl TaskPage {
             .date_formats
             .input_datetime_hint
             .clone();
-        format!("{} or {}", date_hint, datetime_hint)
+        format!(
+            "{} | {} | Tomorrow | Next Fri 5pm | In 3 days | Dec 24 | ...",
+            date_hint, datetime_hint
+        )
     }
 
-    fn get_keybind_hint(&self) -> Line {
+    fn get_keybind_hint(&self) -> Line<'_> {
         let color = self.get_secondary_color();
         let kb = &self.app.borrow().settings.keybindings;
         let i = key!(kb.enter_insert_mode, color);
@@ -184,6 +286,10 @@ impl Page for TaskPage {
                     Constraint::Length(3),
                     Constraint::Length(3),
                     Constraint::Length(3),
+                    Constraint::Length(3),
+                    Constraint::Length(3),
+                    Constraint::Length(3),
+                    Constraint::Min(0),
                 ]
                 .as_ref(),
             )
@@ -220,43 +326,100 @@ impl Page for TaskPage {
 
         // Date
         let curr_text = Text::from(self.task_form.date.clone());
-        let input = Paragraph::new(curr_text).style(self.border_style(1)).block(
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Date ({})", self.get_date_hint()));
+        if let Some(relative) = self.get_relative_date() {
+            block = block.title_bottom(relative);
+        }
+        let input = Paragraph::new(curr_text)
+            .style(self.border_style(1))
+            .block(block);
+        f.render_widget(input, chunks[2]);
+
+        // Time zone and start date
+        let zone = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+            .split(chunks[3]);
+        let curr_text = Text::from(self.task_form.timezone.clone());
+        let input = Paragraph::new(curr_text).style(self.border_style(2)).block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(format!("Date ({})", self.get_date_hint())),
+                .title("Time zone (Europe/Paris | America/New_York | ...)"),
         );
-        f.render_widget(input, chunks[2]);
+        f.render_widget(input, zone[0]);
+
+        let date_hint = &self.app.borrow().settings.date_formats.input_date_hint;
+        let curr_text = Text::from(self.task_form.start_date.clone());
+        let input = Paragraph::new(curr_text).style(self.border_style(3)).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Starts ({})", date_hint)),
+        );
+        f.render_widget(input, zone[1]);
 
         // Repeats
         let curr_text = Text::from(self.task_form.repeats.to_string());
-        let input =
-            Paragraph::new(curr_text)
-                .style(self.border_style(2))
-                .block(Block::default().borders(Borders::ALL).title(
-                "Repeats (Never | Daily | Weekly | Monthly | Yearly | Mon,Tue,Wed,Thu,Fri,Sat,Sun)",
-            ));
-        f.render_widget(input, chunks[3]);
+        let mut block = Block::default().borders(Borders::ALL).title(
+            "Repeats (Daily | Mon,Wed | Every 3 days | Every 2 weeks on Tue,Thu | Last Fri of the month | 15th of the month | FREQ=MONTHLY;BYDAY=-1FR | ...)",
+        );
+        if let Some(preview) = self.get_preview() {
+            block = block.title_bottom(preview);
+        }
+        let input = Paragraph::new(curr_text)
+            .style(self.border_style(4))
+            .block(block);
+        f.render_widget(input, chunks[4]);
+
+        // Repeats from
+        let curr_text = Text::from(self.task_form.repeats_from.clone());
+        let input = Paragraph::new(curr_text).style(self.border_style(5)).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Repeats from (Due date | Completion date)"),
+        );
+        f.render_widget(input, chunks[5]);
+
+        // Repeat until and times left
+        let ends = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+            .split(chunks[6]);
+        let curr_text = Text::from(self.task_form.repeats_until.clone());
+        let input = Paragraph::new(curr_text).style(self.border_style(6)).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Repeat until ({})", date_hint)),
+        );
+        f.render_widget(input, ends[0]);
+
+        let curr_text = Text::from(self.task_form.occurrences_left.clone());
+        let input = Paragraph::new(curr_text)
+            .style(self.border_style(7))
+            .block(Block::default().borders(Borders::ALL).title("Times left"));
+        f.render_widget(input, ends[1]);
 
         // Group
         let curr_text = Text::from(self.task_form.group.clone());
         let input = Paragraph::new(curr_text)
-            .style(self.border_style(3))
+            .style(self.border_style(8))
             .block(Block::default().borders(Borders::ALL).title("Group"));
-        f.render_widget(input, chunks[4]);
+        f.render_widget(input, chunks[7]);
 
         // Description
         let curr_text = Text::from(self.task_form.description.clone());
         let input = Paragraph::new(curr_text)
-            .style(self.border_style(4))
+            .style(self.border_style(9))
             .block(Block::default().borders(Borders::ALL).title("Description"));
-        f.render_widget(input, chunks[5]);
+        f.render_widget(input, chunks[8]);
 
-        // Description
+        // URL
         let curr_text = Text::from(self.task_form.url.clone());
         let input = Paragraph::new(curr_text)
-            .style(self.border_style(5))
+            .style(self.border_style(10))
             .block(Block::default().borders(Borders::ALL).title("URL"));
-        f.render_widget(input, chunks[6]);
+        f.render_widget(input, chunks[9]);
 
         // Place cursor
         if focused {
@@ -270,20 +433,40 @@ impl Page for TaskPage {
                     chunks[2].y + 1,
                 )),
                 2 => f.set_cursor_position((
-                    chunks[3].x + self.task_form.repeats.width() as u16 + 1,
-                    chunks[3].y + 1,
+                    zone[0].x + self.task_form.timezone.width() as u16 + 1,
+                    zone[0].y + 1,
                 )),
                 3 => f.set_cursor_position((
-                    chunks[4].x + self.task_form.group.width() as u16 + 1,
-                    chunks[4].y + 1,
+                    zone[1].x + self.task_form.start_date.width() as u16 + 1,
+                    zone[1].y + 1,
                 )),
                 4 => f.set_cursor_position((
-                    chunks[5].x + self.task_form.description.width() as u16 + 1,
-                    chunks[5].y + 1,
+                    chunks[4].x + self.task_form.repeats.width() as u16 + 1,
+                    chunks[4].y + 1,
                 )),
                 5 => f.set_cursor_position((
-                    chunks[6].x + self.task_form.url.width() as u16 + 1,
-                    chunks[6].y + 1,
+                    chunks[5].x + self.task_form.repeats_from.width() as u16 + 1,
+                    chunks[5].y + 1,
+                )),
+                6 => f.set_cursor_position((
+                    ends[0].x + self.task_form.repeats_until.width() as u16 + 1,
+                    ends[0].y + 1,
+                )),
+                7 => f.set_cursor_position((
+                    ends[1].x + self.task_form.occurrences_left.width() as u16 + 1,
+                    ends[1].y + 1,
+                )),
+                8 => f.set_cursor_position((
+                    chunks[7].x + self.task_form.group.width() as u16 + 1,
+                    chunks[7].y + 1,
+                )),
+                9 => f.set_cursor_position((
+                    chunks[8].x + self.task_form.description.width() as u16 + 1,
+                    chunks[8].y + 1,
+                )),
+                10 => f.set_cursor_position((
+                    chunks[9].x + self.task_form.url.width() as u16 + 1,
+                    chunks[9].y + 1,
                 )),
                 _ => {}
             }
@@ -294,7 +477,14 @@ impl Page for TaskPage {
             let error = Paragraph::new(Text::from(error.to_owned()))
                 .style(Style::default().fg(Color::Red))
                 .block(Block::default().borders(Borders::ALL).title("Error"));
-            f.render_widget(error, chunks[7]);
+            f.render_widget(error, chunks[10]);
+        }
+
+        // Completion history
+        if let Some(history) = self.get_history() {
+            let history = Paragraph::new(history)
+                .block(Block::default().borders(Borders::ALL).title("History"));
+            f.render_widget(history, chunks[11]);
         }
     }
 }
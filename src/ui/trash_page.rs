@@ -0,0 +1,194 @@
+use crate::{app::App, configuration::KeyBindings, key, trash::TrashedTask, utils};
+use anyhow::Result;
+use std::{cell::RefCell, rc::Rc};
+use tui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Cell, Paragraph, Row, Table, Wrap},
+    Frame,
+};
+
+use super::Page;
+
+pub struct TrashPage {
+    /// Most recently deleted first
+    pub trashed: Vec<TrashedTask>,
+    pub selected: usize,
+    pub app: Rc<RefCell<App>>,
+    /// Whether the next `empty_trash` key press empties the trash
+    confirming_empty: bool,
+}
+
+impl TrashPage {
+    pub fn new(app: Rc<RefCell<App>>) -> Result<TrashPage> {
+        let trashed = app.borrow_mut().trashed_tasks()?;
+        Ok(TrashPage {
+            trashed,
+            selected: 0,
+            app,
+            confirming_empty: false,
+        })
+    }
+
+    pub fn next(&mut self) {
+        if self.selected + 1 < self.trashed.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// Moves the selected task back to the todos
+    pub fn restore_selected(&mut self) -> Result<()> {
+        if let Some(trashed) = self.trashed.get(self.selected) {
+            let id = trashed.task.id.unwrap();
+            let name = trashed.task.name.clone();
+            let notice = match self.app.borrow_mut().restore_task(id) {
+                Ok(Some(_)) => format!("Restored '{}'", name),
+                Ok(None) => format!("'{}' is not in the trash anymore", name),
+                Err(e) => e.to_string(),
+            };
+            self.app.borrow_mut().notice = Some(notice);
+        }
+        self.refresh()
+    }
+
+    /// Deletes every task in the trash for good. Asks for confirmation first, by
+    /// pressing the key again
+    pub fn empty(&mut self) -> Result<()> {
+        if self.trashed.is_empty() {
+            self.app.borrow_mut().notice = Some("The trash is already empty".to_string());
+            return Ok(());
+        }
+        if !self.confirming_empty {
+            self.confirming_empty = true;
+            let key = KeyBindings::key_to_str(&self.app.borrow().settings.keybindings.empty_trash);
+            self.app.borrow_mut().notice = Some(format!(
+                "Press '{}' again to delete {} task(s) for good",
+                key,
+                self.trashed.len()
+            ));
+            return Ok(());
+        }
+
+        self.confirming_empty = false;
+        let notice = match self.app.borrow_mut().empty_trash() {
+            Ok(count) => format!("Deleted {} task(s) for good", count),
+            Err(e) => e.to_string(),
+        };
+        self.app.borrow_mut().notice = Some(notice);
+        self.refresh()
+    }
+
+    /// Forgets about a pending `empty`, when any other key is pressed
+    pub fn cancel_empty(&mut self) {
+        self.confirming_empty = false;
+    }
+
+    pub fn refresh(&mut self) -> Result<()> {
+        self.trashed = self.app.borrow_mut().trashed_tasks()?;
+        self.selected = self.selected.min(self.trashed.len().saturating_sub(1));
+        Ok(())
+    }
+
+    fn get_keybind_hint(&self) -> Line<'_> {
+        let color = self.get_secondary_color();
+        let kb = &self.app.borrow().settings.keybindings;
+        let r = key!(kb.restore_task, color);
+        let x = key!(kb.empty_trash, color);
+        let q = key!(kb.quit, color);
+        let b = key!(kb.go_back, color);
+
+        Line::from(vec![
+            Span::raw("Press "),
+            r,
+            Span::raw(" to restore the selected task, "),
+            x,
+            Span::raw(" to empty the trash, "),
+            q,
+            Span::raw(" to quit, and "),
+            b,
+            Span::raw(" to go back to the main screen."),
+        ])
+    }
+
+    pub fn get_primary_color(&self) -> Color {
+        self.app.borrow().settings.colors.primary_color
+    }
+
+    pub fn get_secondary_color(&self) -> Color {
+        self.app.borrow().settings.colors.secondary_color
+    }
+}
+
+impl Page for TrashPage {
+    fn ui(&self, f: &mut Frame, area: Rect, focused: bool) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(2)
+            .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+            .split(area);
+
+        // Draw border around area
+        let border_style = match focused {
+            true => Style::default().fg(self.get_primary_color()),
+            false => Style::default(),
+        };
+        let border_type = match focused {
+            true => BorderType::Thick,
+            false => BorderType::Plain,
+        };
+        let mut title = vec![Span::raw("Trash")];
+        if let Some(notice) = &self.app.borrow().notice {
+            title.push(Span::styled(
+                format!(" - {}", notice),
+                Style::default().fg(self.get_secondary_color()),
+            ));
+        }
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(Line::from(title))
+            .border_style(border_style)
+            .border_type(border_type);
+        f.render_widget(block, area);
+
+        // Keybinds description paragraph
+        let keybinds = Paragraph::new(self.get_keybind_hint())
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        f.render_widget(keybinds, chunks[0]);
+
+        // Deleted tasks
+        let settings = &self.app.borrow().settings;
+        let rows: Vec<Row> = self
+            .trashed
+            .iter()
+            .enumerate()
+            .map(|(idx, trashed)| {
+                let style = match idx == self.selected {
+                    true => Style::default()
+                        .fg(self.get_secondary_color())
+                        .add_modifier(Modifier::BOLD),
+                    false => Style::default().fg(Color::White),
+                };
+                let deleted_at = utils::date_to_display_str(&trashed.deleted_at, true, settings);
+                Row::new(vec![
+                    Cell::from(Span::styled(trashed.task.name.clone(), style)),
+                    Cell::from(Span::styled(
+                        format!("Deleted {}", deleted_at),
+                        Style::default().fg(Color::DarkGray),
+                    )),
+                ])
+            })
+            .collect();
+
+        let table = Table::new(
+            rows,
+            &[Constraint::Percentage(60), Constraint::Percentage(40)],
+        );
+        f.render_widget(table, chunks[1]);
+    }
+}
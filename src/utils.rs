@@ -1,49 +1,299 @@
-use anyhow::{anyhow, Result};
-use chrono::{DateTime, Local, NaiveDate, TimeZone, Timelike};
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, NaiveDateTime, TimeZone};
 
-use crate::app::{App, Id};
+use crate::app::Id;
 use crate::configuration::Settings;
+use crate::day_of_week::DayOfWeek;
+use crate::error::StorageError;
+use crate::migrations::{self, SettingsFile, TasksFile};
+use crate::natural_date;
 use crate::task::Task;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::value::RawValue;
 use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fmt::Display;
 use std::fs;
-use std::path::PathBuf;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
-pub fn load_tasks(file: PathBuf) -> HashMap<Id, Task> {
-    let file = fs::read_to_string(file).expect("Unable to read file");
-    let tasks_map: HashMap<Id, Task> =
-        serde_json::from_str(&file).expect("Unable to parse database file");
-    tasks_map
+#[derive(Deserialize)]
+struct RawTasksFile<'a> {
+    #[serde(borrow)]
+    tasks: HashMap<Id, &'a RawValue>,
 }
 
-pub fn save_tasks(file: PathBuf, app: &App) {
-    let file = fs::File::create(file).expect("Unable to create file");
-    serde_json::to_writer(file, &app.tasks).expect("Unable to write file");
+pub fn load_tasks(file: &Path) -> Result<HashMap<Id, Task>, StorageError> {
+    let mut contents = fs::read_to_string(file).map_err(StorageError::io(file))?;
+
+    // Older files are upgraded on disk first, so that errors point at the file as it is now
+    if let Some(upgraded) = migrations::upgrade_tasks(file, &contents)? {
+        contents = serde_json::to_string(&upgraded).expect("Unable to serialize tasks");
+        write_atomic(file, contents.as_bytes()).map_err(StorageError::io(file))?;
+    }
+
+    // Parse each task on its own so that errors can point at the offending task
+    let raw_tasks: RawTasksFile =
+        serde_json::from_str(&contents).map_err(|e| StorageError::parse(file, &e, None))?;
+
+    raw_tasks
+        .tasks
+        .into_iter()
+        .map(|(id, raw)| match serde_json::from_str::<Task>(raw.get()) {
+            Ok(task) => Ok((id, task)),
+            Err(e) => {
+                let mut error = StorageError::parse(file, &e, Some(id));
+                // serde only knows the position within the task, which starts further on
+                let offset = raw.get().as_ptr() as usize - contents.as_ptr() as usize;
+                let (start_line, start_column) = line_and_column(&contents, offset);
+                if let StorageError::Parse { line, column, .. } = &mut error {
+                    (*line, *column) = match e.line() {
+                        0 => (start_line, start_column),
+                        1 => (start_line, start_column + e.column() - 1),
+                        n => (start_line + n - 1, e.column()),
+                    };
+                }
+                Err(error)
+            }
+        })
+        .collect()
+}
+
+pub fn save_tasks(file: &Path, tasks: &HashMap<Id, Task>) -> Result<(), StorageError> {
+    let contents = serde_json::to_vec(&TasksFile::new(tasks)).expect("Unable to serialize tasks");
+    write_atomic(file, &contents).map_err(StorageError::io(file))
+}
+
+/// Whether the tasks file was written by an older version, and is rewritten by
+/// `load_tasks`
+pub fn tasks_need_upgrade(file: &Path) -> Result<bool, StorageError> {
+    let contents = fs::read_to_string(file).map_err(StorageError::io(file))?;
+    Ok(migrations::upgrade_tasks(file, &contents)?.is_some())
+}
+
+pub fn load_settings(file: &Path) -> Result<Settings, StorageError> {
+    let contents = {
+        let _lock = lock_file(file, false)?;
+        fs::read_to_string(file).map_err(StorageError::io(file))?
+    };
+
+    if migrations::upgrade_settings(file, &contents)?.is_some() {
+        // Read it again under the exclusive lock, in case someone upgraded it meanwhile
+        let _lock = lock_file(file, true)?;
+        return load_settings_locked(file);
+    }
+
+    serde_json::from_str(&contents).map_err(|e| StorageError::parse(file, &e, None))
+}
+
+/// Like `load_settings`, for callers that hold the exclusive lock on `file` already
+pub fn load_settings_locked(file: &Path) -> Result<Settings, StorageError> {
+    let mut contents = fs::read_to_string(file).map_err(StorageError::io(file))?;
+    if let Some(upgraded) = migrations::upgrade_settings(file, &contents)? {
+        contents = serde_json::to_string_pretty(&upgraded).expect("Unable to serialize settings");
+        write_atomic(file, contents.as_bytes()).map_err(StorageError::io(file))?;
+    }
+
+    serde_json::from_str(&contents).map_err(|e| StorageError::parse(file, &e, None))
+}
+
+/// Writes the settings under the exclusive lock, returning the new stamp of the file
+pub fn save_settings(file: &Path, settings: &Settings) -> Result<Option<FileStamp>, StorageError> {
+    let _lock = lock_file(file, true)?;
+    save_settings_locked(file, settings)
+}
+
+/// Like `save_settings`, for callers that hold the exclusive lock on `file` already
+pub fn save_settings_locked(
+    file: &Path,
+    settings: &Settings,
+) -> Result<Option<FileStamp>, StorageError> {
+    let contents = serde_json::to_vec_pretty(&SettingsFile::new(settings))
+        .expect("Unable to serialize settings");
+    write_atomic(file, &contents).map_err(StorageError::io(file))?;
+    file_stamp(file)
+}
+
+/// Converts a byte offset into a 1-based line and column
+fn line_and_column(contents: &str, offset: usize) -> (usize, usize) {
+    let before = &contents[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = offset - before.rfind('\n').map(|i| i + 1).unwrap_or(0) + 1;
+    (line, column)
+}
+
+/// Identifies a version of a file, to tell whether someone else wrote to it
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FileStamp {
+    modified: SystemTime,
+    len: u64,
+    /// `write_atomic` renames a new file into place on every write, so this tells apart
+    /// writes of the same length that the modification time is too coarse for
+    inode: u64,
+}
+
+/// Returns `None` if the file doesn't exist
+pub fn file_stamp(file: &Path) -> Result<Option<FileStamp>, StorageError> {
+    match fs::metadata(file) {
+        Ok(metadata) => Ok(Some(FileStamp {
+            modified: metadata.modified().map_err(StorageError::io(file))?,
+            len: metadata.len(),
+            inode: inode(&metadata),
+        })),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(StorageError::io(file)(e)),
+    }
+}
+
+#[cfg(unix)]
+fn inode(metadata: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.ino()
+}
+
+#[cfg(not(unix))]
+fn inode(_metadata: &fs::Metadata) -> u64 {
+    0
+}
+
+/// Reads a JSON file that other processes write to as well, unless it is still at
+/// `stamp`. A missing file reads as the default value.
+pub fn read_shared<T: DeserializeOwned + Default>(
+    file: &Path,
+    stamp: &mut Option<FileStamp>,
+) -> Result<Option<T>, StorageError> {
+    let _lock = lock_file(file, false)?;
+    read_locked(file, stamp)
+}
+
+/// Like `read_shared`, for callers that hold the lock on `file` already
+pub fn read_locked<T: DeserializeOwned + Default>(
+    file: &Path,
+    stamp: &mut Option<FileStamp>,
+) -> Result<Option<T>, StorageError> {
+    let new_stamp = file_stamp(file)?;
+    if new_stamp == *stamp {
+        return Ok(None);
+    }
+
+    let value = match new_stamp {
+        Some(_) => {
+            let contents = fs::read_to_string(file).map_err(StorageError::io(file))?;
+            serde_json::from_str(&contents).map_err(|e| StorageError::parse(file, &e, None))?
+        }
+        None => T::default(),
+    };
+    *stamp = new_stamp;
+    Ok(Some(value))
+}
+
+/// Writes a JSON file that other processes read as well, returning its new stamp.
+/// Callers hold the exclusive lock on `file`, usually since they read it under the same
+/// lock.
+pub fn write_locked<T: Serialize>(
+    file: &Path,
+    value: &T,
+) -> Result<Option<FileStamp>, StorageError> {
+    let contents = serde_json::to_vec(value).expect("Unable to serialize");
+    write_atomic(file, &contents).map_err(StorageError::io(file))?;
+    file_stamp(file)
+}
+
+/// Takes an advisory lock on `file`, released when the returned handle is dropped.
+///
+/// The lock is held on a sibling `.lock` file, since `write_atomic` replaces the file
+/// itself on every write.
+pub fn lock_file(file: &Path, exclusive: bool) -> Result<fs::File, StorageError> {
+    let lock_path = path_with_suffix(file, "lock");
+    let lock = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .map_err(StorageError::io(&lock_path))?;
+
+    if exclusive {
+        lock.lock().map_err(StorageError::io(&lock_path))?;
+    } else {
+        lock.lock_shared().map_err(StorageError::io(&lock_path))?;
+    }
+    Ok(lock)
+}
+
+/// Creates the directory `file` lives in, if needed
+pub fn ensure_parent_dir(file: &Path) -> Result<(), StorageError> {
+    match file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() && !dir.exists() => {
+            fs::create_dir_all(dir).map_err(StorageError::io(dir))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Appends `.suffix` to the full file name, e.g. `tasks.json` -> `tasks.json.bak`
+pub fn path_with_suffix(file: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(file.as_os_str());
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
 }
 
-pub fn save_settings(file: &PathBuf, settings: &Settings) {
-    let file = fs::File::create(file).expect("Unable to create file");
-    serde_json::to_writer(file, &settings).expect("Unable to write file");
+/// Durably replaces the contents of `file`.
+///
+/// The data is written to a sibling `.tmp` file and fsynced before being renamed over
+/// the original, so a crash at any point leaves either the old or the new contents on
+/// disk. The previous version is kept next to it as `.bak`.
+pub fn write_atomic(file: &Path, contents: &[u8]) -> io::Result<()> {
+    let tmp_file = path_with_suffix(file, "tmp");
+    let mut tmp = fs::File::create(&tmp_file)?;
+    tmp.write_all(contents)?;
+    tmp.sync_all()?;
+    drop(tmp);
+
+    if file.exists() {
+        let backup_file = path_with_suffix(file, "bak");
+        fs::copy(file, &backup_file)?;
+        // The backup has to reach the disk before the original is replaced
+        fs::File::open(&backup_file)?.sync_all()?;
+    }
+
+    fs::rename(&tmp_file, file)?;
+    sync_parent_dir(file)
 }
 
-pub fn date_has_time(date: &DateTime<Local>) -> bool {
-    let time = date.time();
-    if time.hour() == 23 && time.minute() == 59 {
-        return false;
+#[cfg(unix)]
+fn sync_parent_dir(file: &Path) -> io::Result<()> {
+    // The rename is only durable once the directory entry itself has been flushed
+    match file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => fs::File::open(dir)?.sync_all(),
+        _ => Ok(()),
     }
-    true
 }
 
-pub fn date_to_display_str(dt: &DateTime<Local>, settings: &Settings) -> String {
-    let format = if date_has_time(dt) {
+#[cfg(not(unix))]
+fn sync_parent_dir(_file: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+pub fn date_to_display_str(dt: &DateTime<Local>, has_time: bool, settings: &Settings) -> String {
+    let format = if has_time {
         settings.date_formats.display_datetime_format.clone()
     } else {
         settings.date_formats.display_date_format.clone()
     };
-    dt.format(format.as_str()).to_string()
+    settings.locale.format(dt, &format)
 }
 
-pub fn date_to_input_str(dt: &DateTime<Local>, settings: &Settings) -> String {
-    let format = if date_has_time(dt) {
+pub fn date_to_input_str<Tz: TimeZone>(
+    dt: &DateTime<Tz>,
+    has_time: bool,
+    settings: &Settings,
+) -> String
+where
+    Tz::Offset: Display,
+{
+    let format = if has_time {
         settings.date_formats.input_datetime_format.clone()
     } else {
         settings.date_formats.input_date_format.clone()
@@ -51,24 +301,219 @@ pub fn date_to_input_str(dt: &DateTime<Local>, settings: &Settings) -> String {
     dt.format(format.as_str()).to_string()
 }
 
-pub fn get_today() -> DateTime<Local> {
-    let today = Local::now().date_naive().and_hms_opt(23, 59, 59).unwrap();
-    Local.from_local_datetime(&today).unwrap()
+/// When a task is due, relative to today with `humanize_dates`: "today 14:00",
+/// "tomorrow", "in 3 days" or "2 days overdue". Dates more than a few weeks away are
+/// shown in the display format.
+pub fn due_to_human_str(dt: &DateTime<Local>, has_time: bool, settings: &Settings) -> String {
+    if !settings.humanize_dates {
+        return date_to_display_str(dt, has_time, settings);
+    }
+    let days = (dt.date_naive() - Local::now().date_naive()).num_days();
+    let day = match days {
+        0 => "today".to_string(),
+        1 => "tomorrow".to_string(),
+        2..=13 => format!("in {} days", days),
+        14..=55 => format!("in {} weeks", days / 7),
+        -1 => "1 day overdue".to_string(),
+        ..=-2 => format!("{} days overdue", -days),
+        _ => return date_to_display_str(dt, has_time, settings),
+    };
+    match has_time && (0..=1).contains(&days) {
+        true => format!("{} {}", day, dt.format("%-H:%M")),
+        false => day,
+    }
+}
+
+/// When something happened, relative to today with `humanize_dates`: "today 14:00",
+/// "yesterday" or "last Tue". Dates more than a week ago are shown in the display format.
+pub fn past_to_human_str(dt: &DateTime<Local>, has_time: bool, settings: &Settings) -> String {
+    if !settings.humanize_dates {
+        return date_to_display_str(dt, has_time, settings);
+    }
+    let days = (Local::now().date_naive() - dt.date_naive()).num_days();
+    let day = match days {
+        0 => "today".to_string(),
+        1 => "yesterday".to_string(),
+        2..=6 => format!("last {}", settings.locale.format(dt, "%a")),
+        _ => return date_to_display_str(dt, has_time, settings),
+    };
+    match has_time && days <= 1 {
+        true => format!("{} {}", day, dt.format("%-H:%M")),
+        false => day,
+    }
+}
+
+/// The due date of `task` as shown in lists, in the local time zone. With
+/// `show_timezones`, tasks given in another one show the time there too.
+pub fn due_to_display_str(task: &Task, settings: &Settings) -> String {
+    let Some(date) = &task.date else {
+        return "No date".to_string();
+    };
+    let local = due_to_human_str(date, task.has_time, settings);
+    match task.zone() {
+        Some(tz) if settings.show_timezones => {
+            let there = date.with_timezone(&tz);
+            format!("{} ({} {})", local, there.format("%-H:%M"), tz.name())
+        }
+        _ => local,
+    }
 }
 
-pub fn parse_date(s: &str, settings: &Settings) -> Result<DateTime<Local>> {
+/// Tasks that are due on a day rather than at a time of day are due at its very end
+pub fn end_of_day(day: NaiveDate) -> DateTime<Local> {
+    let naive = day.and_hms_opt(23, 59, 59).unwrap();
+    Local.from_local_datetime(&naive).earliest().unwrap()
+}
+
+/// The first day of the week `day` is in, for weeks starting on `week_start`
+pub fn start_of_week(day: NaiveDate, week_start: &DayOfWeek) -> NaiveDate {
+    let days_since = (day.weekday().number_from_monday() + 7 - week_start.to_int()) % 7;
+    day - Days::new(days_since.into())
+}
+
+/// Parses a date in one of the input formats or in natural language. Also returns
+/// whether it has a time of day.
+pub fn parse_date(s: &str, settings: &Settings) -> Result<(DateTime<Local>, bool)> {
     let datetime_format = settings.date_formats.input_datetime_format.as_str();
     let date_format = settings.date_formats.input_date_format.as_str();
 
-    let attempt_datetime = Local.datetime_from_str(s, datetime_format);
+    let attempt_datetime = NaiveDateTime::parse_from_str(s, datetime_format)
+        .ok()
+        .and_then(|naive| Local.from_local_datetime(&naive).earliest());
     let attempt_date = NaiveDate::parse_from_str(s, date_format);
 
-    if let Ok(datetime) = attempt_datetime {
-        Ok(datetime)
+    if let Some(datetime) = attempt_datetime {
+        Ok((datetime, true))
     } else if let Ok(date) = attempt_date {
-        let datetime = date.and_hms_opt(23, 59, 59).unwrap();
-        Ok(Local.from_local_datetime(&datetime).unwrap())
+        Ok((end_of_day(date), false))
     } else {
-        Err(anyhow!("Unable to parse date"))
+        natural_date::parse(s, Local::now(), settings.locale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tasks(names: &[&str]) -> HashMap<Id, Task> {
+        names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| {
+                let mut task = Task::default();
+                task.set_id(Some(id));
+                task.set_name(name.to_string());
+                (id, task)
+            })
+            .collect()
+    }
+
+    fn names(tasks: &HashMap<Id, Task>) -> Vec<String> {
+        let mut names: Vec<_> = tasks.values().map(|t| t.name.clone()).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn stamps_tell_apart_writes_in_the_same_second() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("tasks.json");
+        write_atomic(&file, b"aaaa").unwrap();
+        let before = file_stamp(&file).unwrap().unwrap();
+
+        // Same length and modification time, like two writes within the mtime's granularity
+        write_atomic(&file, b"bbbb").unwrap();
+        let modified = fs::File::options().write(true).open(&file).unwrap();
+        modified.set_modified(before.modified).unwrap();
+        drop(modified);
+
+        let after = file_stamp(&file).unwrap().unwrap();
+        assert_eq!((after.modified, after.len), (before.modified, before.len));
+        assert_ne!(after, before);
+    }
+
+    #[test]
+    fn parse_errors_point_into_the_offending_task() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("tasks.json");
+        let mut value = serde_json::to_value(TasksFile::new(&tasks(&["a", "b"]))).unwrap();
+        value["tasks"]["1"]["complete"] = serde_json::json!("yes");
+        let contents = serde_json::to_string_pretty(&value).unwrap();
+        fs::write(&file, &contents).unwrap();
+
+        let Err(StorageError::Parse {
+            line,
+            column,
+            task_id,
+            ..
+        }) = load_tasks(&file)
+        else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(task_id, Some(1));
+        let error_line = contents.lines().nth(line - 1).unwrap();
+        assert_eq!(error_line.trim(), "\"complete\": \"yes\",");
+        assert_eq!(&error_line[..column], "      \"complete\": \"yes\"");
+    }
+
+    #[test]
+    fn leftover_tmp_file_is_ignored_and_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("tasks.json");
+        save_tasks(&file, &tasks(&["a"])).unwrap();
+
+        // A crash between writing the temporary file and renaming it
+        let tmp_file = path_with_suffix(&file, "tmp");
+        fs::write(&tmp_file, "{\"tasks\": {\"0\": {\"na").unwrap();
+        assert_eq!(names(&load_tasks(&file).unwrap()), ["a"]);
+
+        save_tasks(&file, &tasks(&["a", "b"])).unwrap();
+        assert_eq!(names(&load_tasks(&file).unwrap()), ["a", "b"]);
+        assert!(!tmp_file.exists());
+    }
+
+    #[test]
+    fn previous_version_is_kept_as_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("tasks.json");
+        let backup_file = path_with_suffix(&file, "bak");
+        save_tasks(&file, &tasks(&["a"])).unwrap();
+        assert!(!backup_file.exists());
+        save_tasks(&file, &tasks(&["a", "b"])).unwrap();
+
+        // Truncate the main file, as a partial copy or a full disk would
+        let contents = fs::read(&file).unwrap();
+        fs::write(&file, &contents[..contents.len() / 2]).unwrap();
+        assert!(matches!(load_tasks(&file), Err(StorageError::Parse { .. })));
+
+        fs::copy(&backup_file, &file).unwrap();
+        assert_eq!(names(&load_tasks(&file).unwrap()), ["a"]);
+    }
+
+    #[test]
+    fn corrupt_file_is_replaced_by_the_next_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("tasks.json");
+        fs::write(&file, "not json").unwrap();
+        assert!(load_tasks(&file).is_err());
+
+        save_tasks(&file, &tasks(&["a"])).unwrap();
+        assert_eq!(names(&load_tasks(&file).unwrap()), ["a"]);
+        let backup = fs::read_to_string(path_with_suffix(&file, "bak")).unwrap();
+        assert_eq!(backup, "not json");
+    }
+
+    #[test]
+    fn missing_parent_dir_fails_without_writing() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("missing").join("tasks.json");
+
+        let error = write_atomic(&file, b"{}").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        assert!(!dir.path().join("missing").exists());
+
+        ensure_parent_dir(&file).unwrap();
+        write_atomic(&file, b"{}").unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "{}");
     }
 }